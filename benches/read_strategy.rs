@@ -0,0 +1,49 @@
+//! Cost of a write plus a `read_with` per block under each `ReadStrategy`,
+//! across block sizes, to pick a threshold for `ReadStrategy::Auto`. Writes
+//! are included since lending slots adds a fence to them too. Run with
+//! `cargo bench --bench read_strategy`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use weresocool_ring_buffer::{PacingMode, ReadStrategy, RingBuffer};
+
+const RING_BUFFER_SIZE: usize = 8;
+const SAMPLE_RATE: usize = 48_000;
+const BUDGET: Duration = Duration::from_millis(200);
+
+fn nanos_per_block(buffer_size: usize, strategy: ReadStrategy) -> f64 {
+    let ring =
+        RingBuffer::new(buffer_size, RING_BUFFER_SIZE, SAMPLE_RATE).with_read_strategy(strategy);
+    ring.set_pacing_mode(PacingMode::Unpaced);
+    let block = vec![0.5; buffer_size];
+
+    let mut blocks = 0u32;
+    let mut elapsed = Duration::ZERO;
+    while elapsed < BUDGET {
+        let start = Instant::now();
+        ring.write_slice(&block)
+            .expect("block has buffer_size samples");
+        let sum = ring
+            .read_with(|data, _| data.iter().step_by(64).sum::<f32>())
+            .expect("read_with can't fail on a scratch-sized block");
+        elapsed += start.elapsed();
+
+        black_box(sum);
+        blocks += 1;
+    }
+
+    elapsed.as_nanos() as f64 / blocks as f64
+}
+
+fn main() {
+    println!("{:>8} {:>12} {:>12}", "samples", "copy ns", "borrow ns");
+    for shift in 6..=16 {
+        let buffer_size = 1 << shift;
+        println!(
+            "{:>8} {:>12.1} {:>12.1}",
+            buffer_size,
+            nanos_per_block(buffer_size, ReadStrategy::Copy),
+            nanos_per_block(buffer_size, ReadStrategy::Borrow)
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Oscilloscope drawing the most recent audio while a separate consumer
+//! keeps reading. The scope only uses `read_latest` and `analysis_window`,
+//! which never move the consumer's cursor.
+//!
+//! cargo run --example scope --features minifb
+
+mod support;
+
+use minifb::{Key, Window, WindowOptions};
+use std::sync::Arc;
+use support::{Sine, BUFFER_SIZE, RING_BUFFER_SIZE};
+use weresocool_ring_buffer::RingBuffer;
+
+const SAMPLE_RATE: usize = 48_000;
+const WIDTH: usize = 640;
+const HEIGHT: usize = 240;
+const WINDOW_SAMPLES: usize = BUFFER_SIZE * 4;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let ring = Arc::new(RingBuffer::new(BUFFER_SIZE, RING_BUFFER_SIZE, SAMPLE_RATE));
+    let sine = Sine::new(110.0, SAMPLE_RATE);
+
+    let producer = support::spawn_producer(Arc::clone(&ring), sine);
+    support::wait_until_primed(&ring);
+    let consumer = support::spawn_consumer(Arc::clone(&ring));
+
+    let mut window = Window::new("scope", WIDTH, HEIGHT, WindowOptions::default())?;
+    window.set_target_fps(60);
+
+    let mut latest = vec![0.0; BUFFER_SIZE];
+    let mut samples = vec![0.0; WINDOW_SAMPLES];
+    let mut pixels = vec![0u32; WIDTH * HEIGHT];
+    let mut frames = 0u32;
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        ring.analysis_window(&mut samples);
+        ring.read_latest(&mut latest)?;
+
+        pixels.fill(0);
+        let to_row =
+            |s: f32| ((1.0 - (s * 2.0).clamp(-1.0, 1.0)) * 0.5 * (HEIGHT - 1) as f32) as usize;
+        for (x, (lo, hi)) in support::peak_columns(&samples, WIDTH)
+            .into_iter()
+            .enumerate()
+        {
+            for y in to_row(hi)..=to_row(lo) {
+                pixels[y * WIDTH + x] = 0x00ff_c040;
+            }
+        }
+        window.update_with_buffer(&pixels, WIDTH, HEIGHT)?;
+
+        frames += 1;
+        if frames.is_multiple_of(60) {
+            let peak = latest.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            print!("latest peak: {peak:.3}  ");
+            support::print_stats(&ring);
+        }
+    }
+
+    ring.close();
+    producer.join().expect("producer thread panicked");
+    consumer.join().expect("consumer thread panicked");
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+//! Sine producer feeding a simulated-time consumer, for machines without an
+//! audio device. See `sine_cpal.rs` for the same setup driving real output.
+
+mod support;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use support::{Sine, BUFFER_SIZE, RING_BUFFER_SIZE};
+use weresocool_ring_buffer::RingBuffer;
+
+const SAMPLE_RATE: usize = 48_000;
+
+fn main() {
+    let ring = Arc::new(RingBuffer::new(BUFFER_SIZE, RING_BUFFER_SIZE, SAMPLE_RATE));
+    let sine = Sine::new(440.0, SAMPLE_RATE);
+
+    let producer = support::spawn_producer(Arc::clone(&ring), sine);
+    support::wait_until_primed(&ring);
+
+    // The consumer plays the part of an audio callback: one block per
+    // period, reading into a buffer allocated once up front.
+    let period = Duration::from_secs_f32(BUFFER_SIZE as f32 / SAMPLE_RATE as f32);
+    let mut block = vec![0.0; BUFFER_SIZE];
+    let start = Instant::now();
+    let mut callbacks = 0u32;
+
+    while start.elapsed() < Duration::from_secs(2) {
+        ring.read_into(&mut block)
+            .expect("block has buffer_size samples");
+        callbacks += 1;
+
+        if callbacks.is_multiple_of(20) {
+            let peak = block.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            print!("peak: {peak:.3}  ");
+            support::print_stats(&ring);
+        }
+
+        thread::sleep(period);
+    }
+
+    ring.close();
+    producer.join().expect("producer thread panicked");
+    support::print_stats(&ring);
+}
@@ -0,0 +1,62 @@
+//! Sine producer feeding the default output device through cpal.
+//!
+//! cargo run --example sine_cpal --features cpal
+
+mod support;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use support::{Sine, BUFFER_SIZE, RING_BUFFER_SIZE};
+use weresocool_ring_buffer::RingBuffer;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .ok_or("no output device available")?;
+    let config = device.default_output_config()?;
+    let sample_rate = config.sample_rate().0 as usize;
+    let channels = config.channels() as usize;
+
+    let ring = Arc::new(RingBuffer::new(BUFFER_SIZE, RING_BUFFER_SIZE, sample_rate));
+    let sine = Sine::new(440.0, sample_rate);
+
+    let producer = support::spawn_producer(Arc::clone(&ring), sine);
+    support::wait_until_primed(&ring);
+
+    // The device asks for however many frames it likes, so the callback
+    // keeps a block around and reads a new one when it runs out. Nothing in
+    // here allocates.
+    let reader = Arc::clone(&ring);
+    let mut block = vec![0.0; BUFFER_SIZE];
+    let mut pos = BUFFER_SIZE;
+    let stream = device.build_output_stream(
+        &config.into(),
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            for frame in data.chunks_mut(channels) {
+                if pos == BUFFER_SIZE {
+                    let _ = reader.read_into(&mut block);
+                    pos = 0;
+                }
+                frame.fill(block[pos]);
+                pos += 1;
+            }
+        },
+        |err| eprintln!("stream error: {err}"),
+        None,
+    )?;
+    stream.play()?;
+
+    for _ in 0..8 {
+        thread::sleep(Duration::from_millis(250));
+        support::print_stats(&ring);
+    }
+
+    drop(stream);
+    ring.close();
+    producer.join().expect("producer thread panicked");
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+#![allow(dead_code)]
+
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use weresocool_ring_buffer::RingBuffer;
+
+pub const BUFFER_SIZE: usize = 512;
+pub const RING_BUFFER_SIZE: usize = 16;
+pub const PRIME_BLOCKS: usize = 4;
+
+pub struct Sine {
+    phase: f32,
+    step: f32,
+}
+
+impl Sine {
+    pub fn new(frequency: f32, sample_rate: usize) -> Self {
+        Self {
+            phase: 0.0,
+            step: TAU * frequency / sample_rate as f32,
+        }
+    }
+
+    pub fn fill(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = self.phase.sin() * 0.2;
+            self.phase = (self.phase + self.step) % TAU;
+        }
+    }
+}
+
+/// Blocks until the producer has written its first few blocks, so the
+/// consumer doesn't start on an underrun. Priming happens on the producer
+/// thread, since writing from this one would trip the single-thread check.
+pub fn wait_until_primed(ring: &RingBuffer) {
+    ring.wait_for_writes(PRIME_BLOCKS as u64, None)
+        .expect("ring closed before it was primed");
+}
+
+/// Keeps about `PRIME_BLOCKS` unread blocks in the ring until it's closed.
+pub fn spawn_producer(ring: Arc<RingBuffer>, mut sine: Sine) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let period = ring.min_latency();
+        let mut block = vec![0.0; ring.buffer_size()];
+
+        while !ring.is_closed() {
+            if ring.available() >= PRIME_BLOCKS as u64 {
+                thread::sleep(period / 4);
+                continue;
+            }
+
+            sine.fill(&mut block);
+            if ring.write(block.clone()).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+pub fn print_stats(ring: &RingBuffer) {
+    println!(
+        "writes: {:>6}  reads: {:>6}  available: {:>3}",
+        ring.total_writes(),
+        ring.total_reads(),
+        ring.available()
+    );
+}
+
+/// Reduces `samples` to `columns` (min, max) pairs for drawing a waveform
+/// one pixel column at a time.
+pub fn peak_columns(samples: &[f32], columns: usize) -> Vec<(f32, f32)> {
+    (0..columns)
+        .map(|column| {
+            let start = column * samples.len() / columns;
+            let end = ((column + 1) * samples.len() / columns).max(start + 1);
+            samples
+                .get(start..end.min(samples.len()))
+                .unwrap_or(&[])
+                .iter()
+                .fold((0.0f32, 0.0f32), |(lo, hi), &s| (lo.min(s), hi.max(s)))
+        })
+        .collect()
+}
+
+/// Stands in for an audio callback: one paced read per block period until
+/// the ring is closed.
+pub fn spawn_consumer(ring: Arc<RingBuffer>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let period = ring.min_latency();
+        let mut block = vec![0.0; ring.buffer_size()];
+
+        while !ring.is_closed() {
+            let _ = ring.read_into(&mut block);
+            thread::sleep(period);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peak_columns_bound_each_column() {
+        let samples = [0.5, -0.25, 0.1, 0.2, -0.75, 0.0, 0.3, 0.9];
+        assert_eq!(
+            peak_columns(&samples, 4),
+            [(-0.25, 0.5), (0.0, 0.2), (-0.75, 0.0), (0.0, 0.9)]
+        );
+    }
+
+    #[test]
+    fn more_columns_than_samples_repeat_the_nearest_sample() {
+        assert_eq!(
+            peak_columns(&[1.0, -1.0], 4),
+            [(0.0, 1.0), (0.0, 1.0), (-1.0, 0.0), (-1.0, 0.0)]
+        );
+        assert_eq!(peak_columns(&[], 2), [(0.0, 0.0), (0.0, 0.0)]);
+    }
+}
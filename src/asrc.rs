@@ -0,0 +1,249 @@
+use crate::{RingBuffer, RingBufferError};
+use std::borrow::Borrow;
+
+/// How an [`AsrcReader`] works off a fill level above its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CatchupStrategy {
+    /// Continuously adjust the resampling ratio with a PI controller.
+    Varispeed,
+    /// Play at the nominal rate, but drop one input sample every `rate`
+    /// output samples while the fill level is above target.
+    SampleDrop { rate: usize },
+}
+
+/// Reads from a ring whose producer runs on a different clock than the
+/// consumer. Samples are resampled by a varispeed ratio that a PI controller
+/// nudges to keep the fill level at a target, so latency stays stable while
+/// the two clocks drift. The stream is treated as a single channel.
+pub struct AsrcReader<B: Borrow<RingBuffer>> {
+    ring: B,
+    strategy: CatchupStrategy,
+    until_drop: usize,
+    target_fill: f64,
+    kp: f64,
+    ki: f64,
+    max_deviation: f64,
+    integral: f64,
+    ratio: f64,
+    input: Vec<f32>,
+    block: Vec<f32>,
+    pos: f64,
+}
+
+impl<B: Borrow<RingBuffer>> AsrcReader<B> {
+    /// `target_fill` is the number of unread blocks to hold in the ring.
+    pub fn new(ring: B, target_fill: f64) -> Self {
+        let buffer_size = ring.borrow().buffer_size();
+
+        Self {
+            ring,
+            strategy: CatchupStrategy::Varispeed,
+            until_drop: 0,
+            target_fill,
+            kp: 1e-3,
+            ki: 1e-4,
+            max_deviation: 0.01,
+            integral: 0.0,
+            ratio: 1.0,
+            input: Vec::with_capacity(buffer_size * 2),
+            block: vec![0.0; buffer_size],
+            pos: 0.0,
+        }
+    }
+
+    /// Proportional gain per block of fill error and integral gain per
+    /// block-second of accumulated error.
+    pub fn with_gains(mut self, kp: f64, ki: f64) -> Self {
+        self.kp = kp;
+        self.ki = ki;
+        self
+    }
+
+    pub fn with_catchup_strategy(mut self, strategy: CatchupStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Largest fractional speed change, e.g. `0.01` for +/-1%.
+    pub fn with_max_deviation(mut self, max_deviation: f64) -> Self {
+        self.max_deviation = max_deviation;
+        self
+    }
+
+    /// Input samples consumed per output sample.
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Unread blocks in the ring plus input buffered here, in blocks.
+    pub fn fill_level(&self) -> f64 {
+        let ring = self.ring.borrow();
+        let buffered = (self.input.len() as f64 - self.pos).max(0.0);
+        ring.available() as f64 + buffered / ring.buffer_size() as f64
+    }
+
+    /// Fills `out` with resampled audio. Samples that cannot be produced
+    /// because the ring ran dry are silent.
+    pub fn read_into(&mut self, out: &mut [f32]) -> Result<(), RingBufferError> {
+        let drop_every = match self.strategy {
+            CatchupStrategy::Varispeed => {
+                self.update_ratio(out.len());
+                None
+            }
+            CatchupStrategy::SampleDrop { rate } => {
+                self.ratio = 1.0;
+                (self.fill_level() > self.target_fill).then_some(rate.max(1))
+            }
+        };
+
+        for sample in out.iter_mut() {
+            let index = self.pos as usize;
+            if !self.fill_input(index + 2)? {
+                *sample = 0.0;
+                continue;
+            }
+
+            let frac = (self.pos - index as f64) as f32;
+            *sample = self.input[index] + (self.input[index + 1] - self.input[index]) * frac;
+            self.pos += self.ratio;
+
+            if let Some(rate) = drop_every {
+                self.until_drop += 1;
+                if self.until_drop >= rate {
+                    self.until_drop = 0;
+                    self.pos += 1.0;
+                }
+            }
+        }
+
+        let consumed = (self.pos as usize).min(self.input.len());
+        self.input.drain(..consumed);
+        self.pos -= consumed as f64;
+
+        Ok(())
+    }
+
+    fn update_ratio(&mut self, samples: usize) {
+        let ring = self.ring.borrow();
+        let dt = samples as f64 / ring.sample_rate() as f64;
+        let error = self.fill_level() - self.target_fill;
+
+        // Clamp the integral so it can't wind up beyond what the ratio can use.
+        let limit = if self.ki > 0.0 {
+            self.max_deviation / self.ki
+        } else {
+            0.0
+        };
+        self.integral = (self.integral + error * dt).clamp(-limit, limit);
+
+        let adjustment = self.kp * error + self.ki * self.integral;
+        self.ratio = 1.0 + adjustment.clamp(-self.max_deviation, self.max_deviation);
+    }
+
+    fn fill_input(&mut self, needed: usize) -> Result<bool, RingBufferError> {
+        while self.input.len() < needed {
+            if !self.ring.borrow().read_next_into(&mut self.block)? {
+                return Ok(false);
+            }
+            self.input.extend_from_slice(&self.block);
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Clock, ManualClock};
+    use std::time::Duration;
+
+    const BLOCK: usize = 64;
+    const RATE: usize = 48_000;
+
+    // Runs a producer whose clock is `drift` times faster than the
+    // consumer's for `callbacks` consumer blocks, returning the fill level
+    // after each one.
+    fn simulate(reader: AsrcReader<RingBuffer>, drift: f64, callbacks: usize) -> Vec<f64> {
+        let mut reader = reader;
+        let clock = ManualClock::new();
+        let period = Duration::from_secs_f64(BLOCK as f64 / RATE as f64);
+        let mut out = vec![0.0; BLOCK];
+        let mut written = 0u64;
+        let mut fills = Vec::with_capacity(callbacks);
+
+        for _ in 0..callbacks {
+            clock.advance(period);
+            let due = (clock.now().as_secs_f64() * RATE as f64 * drift) as u64 / BLOCK as u64;
+            let ring = reader.ring.borrow();
+            while written < due {
+                ring.write(vec![0.5; BLOCK]).unwrap();
+                written += 1;
+            }
+
+            reader.read_into(&mut out).unwrap();
+            fills.push(reader.fill_level());
+        }
+
+        fills
+    }
+
+    #[test]
+    fn varispeed_keeps_the_fill_bounded_under_drift() {
+        for drift in [0.995, 1.005] {
+            let ring = RingBuffer::new(BLOCK, 64, RATE);
+            let reader = AsrcReader::new(ring, 8.0).with_gains(1e-3, 1e-3);
+            let fills = simulate(reader, drift, 30_000);
+
+            let settled = &fills[5_000..];
+            let (lo, hi) = settled
+                .iter()
+                .fold((f64::MAX, f64::MIN), |(lo, hi), &f| (lo.min(f), hi.max(f)));
+            assert!(lo > 4.0 && hi < 12.0, "drift {drift}: fill {lo}..{hi}");
+        }
+
+        // without correction the same drift runs the ring into overruns
+        let ring = RingBuffer::new(BLOCK, 64, RATE);
+        let reader = AsrcReader::new(ring, 8.0).with_max_deviation(0.0);
+        let fills = simulate(reader, 1.005, 30_000);
+        assert!(fills[fills.len() - 1] > 60.0);
+    }
+
+    #[test]
+    fn sample_drop_speeds_up_slightly_until_caught_up() {
+        let ring = RingBuffer::new(BLOCK, 64, RATE);
+        for _ in 0..10 {
+            ring.write(vec![0.5; BLOCK]).unwrap();
+        }
+        let mut reader = AsrcReader::new(ring, 2.0)
+            .with_catchup_strategy(CatchupStrategy::SampleDrop { rate: 16 });
+
+        // input samples taken off the ring and used up so far
+        let consumed = |reader: &AsrcReader<RingBuffer>| {
+            let buffered = reader.input.len() as f64 - reader.pos;
+            reader.ring.total_reads() as f64 * BLOCK as f64 - buffered
+        };
+
+        let mut out = vec![0.0; BLOCK];
+        let mut lagging = 0;
+        for _ in 0..400 {
+            // the producer keeps pace, so only the drops work off the lag
+            reader.ring.write(vec![0.5; BLOCK]).unwrap();
+            let before = (consumed(&reader), reader.fill_level());
+            reader.read_into(&mut out).unwrap();
+            let step = consumed(&reader) - before.0;
+
+            if before.1 > 2.0 {
+                lagging += 1;
+                assert_eq!(step, (BLOCK + BLOCK / 16) as f64);
+            } else {
+                assert_eq!(step, BLOCK as f64);
+            }
+        }
+
+        // eleven blocks against a target of two, at four dropped samples
+        // per callback
+        assert_eq!(lagging, 9 * BLOCK / 4);
+        assert!(reader.fill_level() <= 3.0);
+    }
+}
@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Time source used for pacing reads.
+pub trait Clock: Send + Sync {
+    /// Time elapsed since a fixed, arbitrary origin.
+    fn now(&self) -> Duration;
+}
+
+/// Wall clock backed by `std::time::Instant`. `Instant` panics on
+/// `wasm32-unknown-unknown`; use `PerformanceClock` there.
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Clock that only moves when told to, for deterministic pacing. Clones
+/// share the same time.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.nanos.fetch_add(by.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    pub fn set(&self, to: Duration) {
+        self.nanos.store(to.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+/// Browser clock backed by `performance.now()`, which is monotonic and
+/// available on the main thread and in workers. Build for
+/// `wasm32-unknown-unknown` with the `wasm` feature and pass it to
+/// [`RingBuffer::new_with_clock`](crate::RingBuffer::new_with_clock).
+#[cfg(feature = "wasm")]
+pub struct PerformanceClock {
+    origin: f64,
+}
+
+#[cfg(feature = "wasm")]
+impl PerformanceClock {
+    pub fn new() -> Self {
+        Self {
+            origin: performance_now(),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl Default for PerformanceClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl Clock for PerformanceClock {
+    fn now(&self) -> Duration {
+        Duration::from_secs_f64((performance_now() - self.origin).max(0.0) / 1000.0)
+    }
+}
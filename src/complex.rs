@@ -0,0 +1,61 @@
+use crate::{ReadOutcome, RingBuffer, RingBufferError};
+use num_complex::Complex;
+
+impl RingBuffer {
+    /// Writes one spectrum as interleaved `re, im` pairs, so `buffer_size`
+    /// must be twice the number of bins. Build the ring with
+    /// `with_channels(2)` to keep frame-based helpers in step.
+    ///
+    /// There is no `ComplexRingBuffer` type: the ring stores `f32` samples
+    /// rather than a generic element, so a plain [`RingBuffer`] holds the
+    /// packed spectra.
+    pub fn write_complex(&self, spectrum: &[Complex<f32>]) -> Result<(), RingBufferError> {
+        if spectrum.len() * 2 != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        let data = spectrum.iter().flat_map(|c| [c.re, c.im]).collect();
+        self.write(data)
+    }
+
+    /// Paced read of one spectrum written by
+    /// [`write_complex`](Self::write_complex).
+    pub fn read_complex_into(
+        &self,
+        out: &mut [Complex<f32>],
+    ) -> Result<ReadOutcome, RingBufferError> {
+        // Complex<f32> is repr(C) { re, im }, so the bins are laid out
+        // exactly like the interleaved block.
+        let samples =
+            unsafe { std::slice::from_raw_parts_mut(out.as_mut_ptr() as *mut f32, out.len() * 2) };
+        self.read_into(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PacingMode;
+
+    #[test]
+    fn spectra_round_trip() {
+        let ring = RingBuffer::new(8, 4, 1000).with_channels(2);
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        let spectrum = [
+            Complex::new(1.0, 0.0),
+            Complex::new(0.5, -0.5),
+            Complex::new(0.0, 0.25),
+            Complex::new(-0.125, 0.0),
+        ];
+        ring.write_complex(&spectrum).unwrap();
+
+        let mut out = [Complex::new(f32::NAN, f32::NAN); 4];
+        assert!(ring.read_complex_into(&mut out).unwrap().fresh);
+        assert_eq!(out, spectrum);
+
+        assert!(matches!(
+            ring.write_complex(&spectrum[..3]),
+            Err(RingBufferError::DataSizeMismatch)
+        ));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::RingBuffer;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Returned by a [`ProducerDriver`] fill closure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillStatus {
+    /// The block was filled and should be published.
+    Continue,
+    /// The stream has ended; the block is discarded and the ring closed.
+    EndOfStream,
+}
+
+/// Runs a producer thread that fills a block whenever the ring has room.
+pub struct ProducerDriver;
+
+impl ProducerDriver {
+    /// Spawns a thread that waits for free capacity, fills a block in place
+    /// with `fill` and publishes it, until `fill` reports end of stream, the
+    /// ring is closed or the handle is stopped. The thread sleeps between
+    /// blocks and is woken by the reader freeing capacity.
+    pub fn spawn<F>(ring: Arc<RingBuffer>, mut fill: F) -> DriverHandle
+    where
+        F: FnMut(&mut [f32]) -> FillStatus + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let thread = thread::spawn(move || {
+            // If fill panics the consumer would otherwise wait forever.
+            let _close_on_panic = CloseOnPanic(&ring);
+
+            while !thread_stop.load(Ordering::SeqCst) && !ring.is_closed() {
+                if ring.free_capacity() == 0 {
+                    ring.wait_for_capacity(None);
+                    continue;
+                }
+
+                let mut block = ring.write_guard();
+                match fill(&mut block) {
+                    FillStatus::Continue => {
+                        if block.commit().is_err() {
+                            break;
+                        }
+                    }
+                    FillStatus::EndOfStream => {
+                        ring.close();
+                        break;
+                    }
+                }
+            }
+        });
+
+        DriverHandle { stop, thread }
+    }
+}
+
+struct CloseOnPanic<'a>(&'a RingBuffer);
+
+impl Drop for CloseOnPanic<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.close();
+        }
+    }
+}
+
+/// Handle to a [`ProducerDriver`] thread.
+pub struct DriverHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl DriverHandle {
+    /// Stops the driver without closing the ring and waits for the thread.
+    /// A panic in the fill closure is returned as the error.
+    pub fn stop(self) -> thread::Result<()> {
+        self.stop.store(true, Ordering::SeqCst);
+        self.thread.thread().unpark();
+        self.thread.join()
+    }
+
+    /// Waits for the driver to finish on its own, e.g. at end of stream.
+    pub fn join(self) -> thread::Result<()> {
+        self.thread.join()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PacingMode;
+    use std::time::Duration;
+
+    fn unpaced_ring() -> Arc<RingBuffer> {
+        let ring = Arc::new(RingBuffer::new(4, 4, 1000));
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        ring
+    }
+
+    #[test]
+    fn every_block_is_delivered_exactly_once() {
+        let ring = unpaced_ring();
+        let mut next = 0;
+        let driver = ProducerDriver::spawn(Arc::clone(&ring), move |block| {
+            if next == 100 {
+                return FillStatus::EndOfStream;
+            }
+            block.fill(next as f32);
+            next += 1;
+            FillStatus::Continue
+        });
+
+        let mut seen = Vec::new();
+        let mut out = [0.0; 4];
+        while !ring.is_closed() || ring.available() > 0 {
+            if ring.read_next_into(&mut out).unwrap() {
+                seen.push(out[0] as usize);
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        assert!(driver.join().is_ok());
+        assert_eq!(seen, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stop_leaves_the_ring_open() {
+        let ring = unpaced_ring();
+        let driver = ProducerDriver::spawn(Arc::clone(&ring), |block| {
+            block.fill(1.0);
+            FillStatus::Continue
+        });
+
+        // let it fill the ring and park waiting for capacity
+        ring.wait_for_writes(4, Some(Duration::from_secs(5)))
+            .unwrap();
+        assert!(driver.stop().is_ok());
+        assert!(!ring.is_closed());
+        assert_eq!(ring.total_writes(), 4);
+    }
+
+    #[test]
+    fn a_panicking_fill_closes_the_ring_and_surfaces_in_join() {
+        let ring = unpaced_ring();
+        let mut calls = 0;
+        let driver = ProducerDriver::spawn(Arc::clone(&ring), move |block| {
+            calls += 1;
+            assert!(calls < 3, "fill failed");
+            block.fill(1.0);
+            FillStatus::Continue
+        });
+
+        let err = driver.join().unwrap_err();
+        assert_eq!(*err.downcast_ref::<&str>().unwrap(), "fill failed");
+        assert!(ring.is_closed());
+        assert_eq!(ring.total_writes(), 2);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{ReadOutcome, RingBuffer, RingBufferError};
+use std::io;
+
+/// First word of every frame, `b"WSRB"` read as a little-endian `u32`.
+pub const FRAME_MAGIC: u32 = u32::from_le_bytes(*b"WSRB");
+
+/// Bytes before the samples: magic, sample rate and buffer size, each a
+/// little-endian `u32`.
+pub const FRAME_HEADER_LEN: usize = 12;
+
+impl RingBuffer {
+    /// Paced read like [`read_into`](Self::read_into), appending the block
+    /// to `out` as one frame: the header followed by the samples as
+    /// little-endian `f32`.
+    pub fn read_framed_into(&self, out: &mut Vec<u8>) -> Result<ReadOutcome, RingBufferError> {
+        let mut block = vec![0.0; self.buffer_size];
+        let outcome = self.read_into(&mut block)?;
+
+        out.reserve(FRAME_HEADER_LEN + block.len() * 4);
+        out.extend_from_slice(&FRAME_MAGIC.to_le_bytes());
+        out.extend_from_slice(&(self.sample_rate as u32).to_le_bytes());
+        out.extend_from_slice(&(self.buffer_size as u32).to_le_bytes());
+        for sample in &block {
+            out.extend_from_slice(&sample.to_le_bytes());
+        }
+
+        Ok(outcome)
+    }
+
+    /// Writes one frame produced by [`read_framed_into`](Self::read_framed_into).
+    /// Errors with `FrameMismatch` if the magic, sample rate or buffer size
+    /// in the header don't match this buffer, and `DataSizeMismatch` if the
+    /// samples that follow aren't exactly one block.
+    pub fn write_framed(&self, bytes: &[u8]) -> Result<(), RingBufferError> {
+        if bytes.len() < FRAME_HEADER_LEN {
+            return Err(RingBufferError::FrameMismatch);
+        }
+
+        let (header, samples) = bytes.split_at(FRAME_HEADER_LEN);
+        let word = |i: usize| u32::from_le_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+        if word(0) != FRAME_MAGIC
+            || word(1) != self.sample_rate as u32
+            || word(2) as usize != self.buffer_size
+        {
+            return Err(RingBufferError::FrameMismatch);
+        }
+
+        if samples.len() != self.buffer_size * 4 {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        let data = samples
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        self.write(data)
+    }
+
+    /// Drains every unread block into `writer` as raw `f32` samples,
+    /// little-endian if `samples_le`, big-endian otherwise, with no framing.
+    /// Returns the number of bytes written. A block that fails to write has
+    /// already been claimed and is lost.
+    pub fn pipe_to<W: io::Write>(&self, writer: &mut W, samples_le: bool) -> io::Result<usize> {
+        let mut block = vec![0.0; self.buffer_size];
+        let mut bytes = Vec::with_capacity(self.buffer_size * 4);
+        let mut written = 0;
+        while self.claim_next_into(&mut block) {
+            bytes.clear();
+            for sample in &block {
+                if samples_le {
+                    bytes.extend_from_slice(&sample.to_le_bytes());
+                } else {
+                    bytes.extend_from_slice(&sample.to_be_bytes());
+                }
+            }
+            writer.write_all(&bytes)?;
+            written += bytes.len();
+        }
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PacingMode, RingBuffer, RingBufferError};
+
+    #[test]
+    fn frames_round_trip_into_a_matching_ring() {
+        let source = RingBuffer::new(2, 4, 1000);
+        source.set_pacing_mode(PacingMode::Unpaced);
+        source.write(vec![0.5, -0.75]).unwrap();
+
+        let mut frame = Vec::new();
+        assert!(source.read_framed_into(&mut frame).unwrap().fresh);
+        assert_eq!(frame.len(), super::FRAME_HEADER_LEN + 8);
+
+        let sink = RingBuffer::new(2, 4, 1000);
+        sink.set_pacing_mode(PacingMode::Unpaced);
+        sink.write_framed(&frame).unwrap();
+        assert_eq!(sink.read(), [0.5, -0.75]);
+
+        // geometry mismatches are rejected before anything is written
+        for other in [RingBuffer::new(3, 4, 1000), RingBuffer::new(2, 4, 48_000)] {
+            assert!(matches!(
+                other.write_framed(&frame),
+                Err(RingBufferError::FrameMismatch)
+            ));
+            assert_eq!(other.total_writes(), 0);
+        }
+        assert!(matches!(
+            sink.write_framed(&frame[..frame.len() - 4]),
+            Err(RingBufferError::DataSizeMismatch)
+        ));
+        frame[0] ^= 1;
+        assert!(matches!(
+            sink.write_framed(&frame),
+            Err(RingBufferError::FrameMismatch)
+        ));
+    }
+
+    #[test]
+    fn pipe_to_writes_raw_samples_in_order() {
+        let ring = RingBuffer::new(2, 4, 1000);
+        ring.write(vec![1.0, -2.0]).unwrap();
+        ring.write(vec![0.5, 0.25]).unwrap();
+
+        let mut le = Vec::new();
+        assert_eq!(ring.pipe_to(&mut le, true).unwrap(), 16);
+        let expected: Vec<u8> = [1.0f32, -2.0, 0.5, 0.25]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        assert_eq!(le, expected);
+        assert_eq!(ring.available(), 0);
+
+        ring.write(vec![3.0, 4.0]).unwrap();
+        let mut be = Vec::new();
+        assert_eq!(ring.pipe_to(&mut be, false).unwrap(), 8);
+        assert_eq!(be, [3.0f32.to_be_bytes(), 4.0f32.to_be_bytes()].concat());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::RingBuffer;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// Coarse verdict on how the buffer is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Health {
+    Ok,
+    Warning,
+    Critical,
+}
+
+/// Unread-block counts at which the verdict degrades. The last paced read
+/// underrunning also counts as a warning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthThreshold {
+    pub warning_lag: u64,
+    pub critical_lag: u64,
+}
+
+/// Passed to the health callback when the verdict changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthReport {
+    pub health: Health,
+    pub previous: Health,
+    pub available: u64,
+    pub underruns: u64,
+}
+
+pub(crate) struct HealthWatch {
+    threshold: HealthThreshold,
+    callback: Box<dyn Fn(HealthReport) + Send + Sync>,
+}
+
+impl Health {
+    fn from_u8(health: u8) -> Health {
+        match health {
+            0 => Health::Ok,
+            1 => Health::Warning,
+            _ => Health::Critical,
+        }
+    }
+}
+
+impl RingBuffer {
+    pub fn health(&self, threshold: &HealthThreshold) -> Health {
+        let available = self.available();
+        if available >= threshold.critical_lag {
+            Health::Critical
+        } else if available >= threshold.warning_lag
+            || self.last_read_underrun.load(Ordering::Relaxed)
+        {
+            Health::Warning
+        } else {
+            Health::Ok
+        }
+    }
+
+    /// Calls `callback` whenever the verdict for `threshold` changes, as
+    /// seen after a read or write. It runs on whichever thread made that
+    /// read or write, so it should be quick.
+    pub fn set_health_callback(
+        &self,
+        threshold: HealthThreshold,
+        callback: Box<dyn Fn(HealthReport) + Send + Sync>,
+    ) {
+        let last = self.health(&threshold);
+        self.last_health.store(last as u8, Ordering::Relaxed);
+        *self.health_watch.lock().unwrap() = Some(Arc::new(HealthWatch {
+            threshold,
+            callback,
+        }));
+    }
+
+    pub(crate) fn check_health(&self) {
+        // held just long enough to clone the Arc, so the callback can
+        // replace itself
+        let Some(watch) = self.health_watch.lock().unwrap().clone() else {
+            return;
+        };
+
+        // the reader and writer may both see the change; only the one that
+        // records it reports it
+        let health = self.health(&watch.threshold);
+        let mut last = self.last_health.load(Ordering::Relaxed);
+        loop {
+            if health as u8 == last {
+                return;
+            }
+            match self.last_health.compare_exchange_weak(
+                last,
+                health as u8,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => last = actual,
+            }
+        }
+
+        (watch.callback)(HealthReport {
+            health,
+            previous: Health::from_u8(last),
+            available: self.available(),
+            underruns: self.underruns(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PacingMode;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn the_callback_fires_only_on_transitions() {
+        let ring = RingBuffer::new(4, 16, 1000);
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        ring.set_health_callback(
+            HealthThreshold {
+                warning_lag: 3,
+                critical_lag: 6,
+            },
+            Box::new(move |report| sink.lock().unwrap().push(report)),
+        );
+
+        for _ in 0..7 {
+            ring.write(vec![0.0; 4]).unwrap();
+        }
+        for _ in 0..6 {
+            ring.read();
+        }
+
+        let transitions: Vec<_> = reports
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|report| (report.previous, report.health, report.available))
+            .collect();
+        assert_eq!(
+            transitions,
+            [
+                (Health::Ok, Health::Warning, 3),
+                (Health::Warning, Health::Critical, 6),
+                (Health::Critical, Health::Warning, 5),
+                (Health::Warning, Health::Ok, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_callback_can_replace_itself() {
+        let ring = Arc::new(RingBuffer::new(4, 16, 1000));
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        let threshold = HealthThreshold {
+            warning_lag: 1,
+            critical_lag: 2,
+        };
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let weak = Arc::downgrade(&ring);
+        ring.set_health_callback(
+            threshold,
+            Box::new(move |report| {
+                let sink = Arc::clone(&sink);
+                sink.lock().unwrap().push(("first", report.health));
+                // called from inside a write, which would deadlock if the
+                // callback slot were still locked
+                weak.upgrade().unwrap().set_health_callback(
+                    threshold,
+                    Box::new(move |report| sink.lock().unwrap().push(("second", report.health))),
+                );
+            }),
+        );
+
+        ring.write(vec![0.0; 4]).unwrap();
+        ring.write(vec![0.0; 4]).unwrap();
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [("first", Health::Warning), ("second", Health::Critical)]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{ReadOutcome, RingBuffer, RingBufferError};
+
+/// How integer reads turn a scaled sample into an integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Drop the fraction, so `2.7` becomes `2` and `-2.7` becomes `-2`.
+    TruncateTowardZero,
+    /// Round to the nearest integer, ties to the even one, so `0.5` and
+    /// `-0.5` become `0` and `1.5` becomes `2`. The default: it adds no
+    /// DC offset and matches what most converters do.
+    #[default]
+    RoundHalfToEven,
+}
+
+impl RoundingMode {
+    fn apply(self, value: f32) -> f32 {
+        match self {
+            RoundingMode::TruncateTowardZero => value.trunc(),
+            RoundingMode::RoundHalfToEven => value.round_ties_even(),
+        }
+    }
+}
+
+// Scales full-scale [-1, 1] to `bits`-bit integers, rounds, and saturates
+// to the integer range, so 1.0 becomes the largest positive value.
+fn to_int(sample: f32, bits: u32, mode: RoundingMode) -> i32 {
+    let scale = (1i64 << (bits - 1)) as f32;
+    let max = scale - 1.0;
+    mode.apply(sample * scale).clamp(-scale, max) as i32
+}
+
+impl RingBuffer {
+    /// Paced read like [`read_into`](Self::read_into), converted to 16-bit
+    /// PCM with saturation. Converts straight from the ring's own read
+    /// buffer, so nothing is allocated per read.
+    pub fn read_i16_into(
+        &self,
+        out: &mut [i16],
+        mode: RoundingMode,
+    ) -> Result<ReadOutcome, RingBufferError> {
+        if out.len() != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        self.read_with(|block, outcome| {
+            for (out, &sample) in out.iter_mut().zip(block) {
+                *out = to_int(sample, 16, mode) as i16;
+            }
+            outcome
+        })
+    }
+
+    /// Paced read converted to 24-bit PCM with saturation, each sample in
+    /// the low 24 bits of an `i32`.
+    pub fn read_i24_into(
+        &self,
+        out: &mut [i32],
+        mode: RoundingMode,
+    ) -> Result<ReadOutcome, RingBufferError> {
+        if out.len() != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        self.read_with(|block, outcome| {
+            for (out, &sample) in out.iter_mut().zip(block) {
+                *out = to_int(sample, 24, mode);
+            }
+            outcome
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PacingMode;
+
+    // Fractions of one 16-bit step, then full scale and beyond.
+    const SAMPLES: [f32; 10] = [
+        0.49999 / 32768.0,
+        0.5 / 32768.0,
+        -0.5 / 32768.0,
+        1.5 / 32768.0,
+        2.5 / 32768.0,
+        -2.7 / 32768.0,
+        1.0,
+        -1.0,
+        2.0,
+        -3.0,
+    ];
+
+    fn read_i16(mode: RoundingMode) -> [i16; 10] {
+        let ring = RingBuffer::new(10, 4, 1000);
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        ring.write(SAMPLES.to_vec()).unwrap();
+
+        let mut out = [0; 10];
+        ring.read_i16_into(&mut out, mode).unwrap();
+        out
+    }
+
+    #[test]
+    fn each_mode_rounds_and_saturates() {
+        assert_eq!(
+            read_i16(RoundingMode::RoundHalfToEven),
+            [0, 0, 0, 2, 2, -3, 32767, -32768, 32767, -32768]
+        );
+        assert_eq!(
+            read_i16(RoundingMode::TruncateTowardZero),
+            [0, 0, 0, 1, 2, -2, 32767, -32768, 32767, -32768]
+        );
+    }
+
+    #[test]
+    fn twenty_four_bit_saturates_at_its_own_range() {
+        let half_step = 0.5 / (1 << 23) as f32;
+        for mode in [
+            RoundingMode::RoundHalfToEven,
+            RoundingMode::TruncateTowardZero,
+        ] {
+            assert_eq!(to_int(half_step, 24, mode), 0);
+            assert_eq!(to_int(-half_step, 24, mode), 0);
+            assert_eq!(to_int(1.0, 24, mode), (1 << 23) - 1);
+            assert_eq!(to_int(-4.0, 24, mode), -(1 << 23));
+        }
+        assert_eq!(
+            to_int(3.0 * half_step, 24, RoundingMode::RoundHalfToEven),
+            2
+        );
+        assert_eq!(
+            to_int(3.0 * half_step, 24, RoundingMode::TruncateTowardZero),
+            1
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::RingBuffer;
+use std::fmt;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// An internal inconsistency found by [`RingBuffer::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    ReadsAheadOfWrites { total_reads: u64, total_writes: u64 },
+    NullSlot { index: usize },
+    SlotLength { index: usize, len: usize },
+    LastReadInFuture { last_read: Duration, now: Duration },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvariantViolation::ReadsAheadOfWrites {
+                total_reads,
+                total_writes,
+            } => write!(
+                f,
+                "{} reads is ahead of {} writes",
+                total_reads, total_writes
+            ),
+            InvariantViolation::NullSlot { index } => write!(f, "slot {} is null", index),
+            InvariantViolation::SlotLength { index, len } => {
+                write!(f, "slot {} holds {} samples", index, len)
+            }
+            InvariantViolation::LastReadInFuture { last_read, now } => write!(
+                f,
+                "last read at {:?} is after the current time {:?}",
+                last_read, now
+            ),
+        }
+    }
+}
+
+impl RingBuffer {
+    /// Checks the buffer's internal consistency and returns every violation
+    /// found. Intended for diagnostics; it inspects every slot.
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = Vec::new();
+
+        // reads first: writes only grow, so a stale writes can't look behind
+        let total_reads = self.total_reads.load(Ordering::Relaxed);
+        let total_writes = self.total_writes.load(Ordering::Relaxed);
+        if total_reads > total_writes {
+            violations.push(InvariantViolation::ReadsAheadOfWrites {
+                total_reads,
+                total_writes,
+            });
+        }
+
+        for (index, slot) in self.buffers.iter().enumerate() {
+            let data_ptr = slot.load(Ordering::Acquire);
+            if data_ptr.is_null() {
+                violations.push(InvariantViolation::NullSlot { index });
+                continue;
+            }
+
+            let len = self.pin_slot(slot).len();
+            if len != self.buffer_size {
+                violations.push(InvariantViolation::SlotLength { index, len });
+            }
+        }
+
+        let last_read = self.last_read.load(Ordering::Relaxed);
+        let now = self.now_nanos();
+        if last_read > now {
+            violations.push(InvariantViolation::LastReadInFuture {
+                last_read: Duration::from_nanos(last_read),
+                now: Duration::from_nanos(now),
+            });
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    // The cheap subset of validate, for the end of every read and write.
+    pub(crate) fn debug_assert_invariants(&self) {
+        if cfg!(debug_assertions) {
+            let total_reads = self.total_reads.load(Ordering::Relaxed);
+            let total_writes = self.total_writes.load(Ordering::Relaxed);
+            debug_assert!(
+                total_reads <= total_writes,
+                "{} reads is ahead of {} writes",
+                total_reads,
+                total_writes
+            );
+            let last_read = self.last_read.load(Ordering::Relaxed);
+            let now = self.now_nanos();
+            debug_assert!(
+                last_read <= now,
+                "last read at {:?} is after the current time {:?}",
+                Duration::from_nanos(last_read),
+                Duration::from_nanos(now)
+            );
+        }
+    }
+
+    // A claimed block must have been written and must still be in its slot,
+    // so it lags the writes it was chosen against by at most the ring size.
+    // Called on every claim; total_reads alone can lag further when the
+    // reader stalls, so this isn't part of debug_assert_invariants.
+    pub(crate) fn debug_assert_claim(&self, seq: u64, total_writes: u64) {
+        if cfg!(debug_assertions) {
+            let ring = self.ring_buffer_size as u64;
+            debug_assert!(
+                seq < total_writes,
+                "claimed {} with only {} writes",
+                seq,
+                total_writes
+            );
+            debug_assert!(
+                total_writes - seq <= ring,
+                "claimed {} lags {} writes by more than the ring",
+                seq,
+                total_writes
+            );
+        }
+    }
+
+    /// Overwrites the position counters, bypassing every check.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn set_counters_for_test(&self, total_writes: u64, total_reads: u64) {
+        self.total_writes.store(total_writes, Ordering::Relaxed);
+        self.writes_begun.store(total_writes, Ordering::Relaxed);
+        self.total_reads.store(total_reads, Ordering::Relaxed);
+    }
+
+    /// Replaces the storage of slot `index` with `data`, whatever its length.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn replace_slot_for_test(&self, index: usize, data: Vec<f32>) {
+        let old_data = self.buffers[index].swap(Box::into_raw(Box::new(data)), Ordering::Release);
+        unsafe {
+            _ = Box::from_raw(old_data);
+        }
+    }
+
+    /// Overwrites the time of the last paced advance.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn set_last_read_for_test(&self, last_read: Duration) {
+        self.last_read
+            .store(last_read.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManualClock;
+    use std::ptr;
+
+    fn written_ring() -> (RingBuffer, ManualClock) {
+        let clock = ManualClock::new();
+        let ring = RingBuffer::new_with_clock(4, 4, 1000, clock.clone());
+        ring.write(vec![1.0; 4]).unwrap();
+        clock.advance(Duration::from_millis(10));
+        (ring, clock)
+    }
+
+    #[test]
+    fn a_healthy_ring_validates() {
+        assert_eq!(written_ring().0.validate(), Ok(()));
+    }
+
+    #[test]
+    fn each_corruption_is_reported() {
+        let (ring, _clock) = written_ring();
+        ring.set_counters_for_test(2, 5);
+        assert_eq!(
+            ring.validate(),
+            Err(vec![InvariantViolation::ReadsAheadOfWrites {
+                total_reads: 5,
+                total_writes: 2
+            }])
+        );
+
+        let (ring, _clock) = written_ring();
+        ring.replace_slot_for_test(3, vec![0.0; 7]);
+        assert_eq!(
+            ring.validate(),
+            Err(vec![InvariantViolation::SlotLength { index: 3, len: 7 }])
+        );
+
+        let (ring, _clock) = written_ring();
+        ring.set_last_read_for_test(Duration::from_secs(1));
+        assert_eq!(
+            ring.validate(),
+            Err(vec![InvariantViolation::LastReadInFuture {
+                last_read: Duration::from_secs(1),
+                now: Duration::from_millis(10)
+            }])
+        );
+
+        // no hook can null a slot, so swap one out and put it back
+        let (ring, _clock) = written_ring();
+        let data_ptr = ring.buffers[1].swap(ptr::null_mut(), Ordering::AcqRel);
+        let result = ring.validate();
+        ring.buffers[1].store(data_ptr, Ordering::Release);
+        assert_eq!(result, Err(vec![InvariantViolation::NullSlot { index: 1 }]));
+    }
+
+    #[test]
+    fn violations_are_all_collected() {
+        let (ring, _clock) = written_ring();
+        ring.set_counters_for_test(0, 1);
+        ring.replace_slot_for_test(0, Vec::new());
+        assert_eq!(ring.validate().unwrap_err().len(), 2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "5 reads is ahead of 3 writes")]
+    fn the_next_write_asserts_on_reads_ahead_of_writes() {
+        let (ring, _clock) = written_ring();
+        ring.set_counters_for_test(2, 5);
+        let _ = ring.write(vec![0.0; 4]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is after the current time")]
+    fn a_last_read_in_the_future_is_asserted() {
+        let (ring, _clock) = written_ring();
+        ring.set_last_read_for_test(Duration::from_secs(1));
+        ring.debug_assert_invariants();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "claimed 4 with only 4 writes")]
+    fn claiming_an_unwritten_block_is_asserted() {
+        let (ring, _clock) = written_ring();
+        ring.debug_assert_claim(4, 4);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "claimed 1 lags 6 writes by more than the ring")]
+    fn claiming_a_lapped_block_is_asserted() {
+        let (ring, _clock) = written_ring();
+        ring.debug_assert_claim(1, 6);
+    }
+}
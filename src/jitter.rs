@@ -0,0 +1,158 @@
+use crate::{RingBuffer, RingBufferError};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Glitches injected by a [`JitteryRingBuffer`].
+#[derive(Debug, Clone)]
+pub struct JitterConfig {
+    /// Each write sleeps for a random time up to this long.
+    pub max_write_latency: Duration,
+    /// Probability in `[0.0, 1.0]` that a write is silently dropped.
+    pub drop_rate: f32,
+    /// Number of writes a block is held back before it becomes visible.
+    pub visibility_delay: usize,
+    /// Seed for the RNG, so runs are reproducible.
+    pub seed: u64,
+}
+
+impl Default for JitterConfig {
+    fn default() -> Self {
+        Self {
+            max_write_latency: Duration::ZERO,
+            drop_rate: 0.0,
+            visibility_delay: 0,
+            seed: 0x5eed,
+        }
+    }
+}
+
+// xorshift64*, good enough for deciding which writes to mangle.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Wraps a [`RingBuffer`] and makes its producer side flaky, for testing how
+/// consumers cope with late, missing or delayed blocks. Consumers read
+/// through [`inner`](Self::inner).
+pub struct JitteryRingBuffer {
+    inner: RingBuffer,
+    config: JitterConfig,
+    rng: Mutex<Rng>,
+    pending: Mutex<VecDeque<Vec<f32>>>,
+    dropped: AtomicU64,
+}
+
+impl JitteryRingBuffer {
+    pub fn new(inner: RingBuffer, config: JitterConfig) -> Self {
+        Self {
+            inner,
+            rng: Mutex::new(Rng::new(config.seed)),
+            config,
+            pending: Mutex::new(VecDeque::new()),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    pub fn inner(&self) -> &RingBuffer {
+        &self.inner
+    }
+
+    pub fn write(&self, data: Vec<f32>) -> Result<(), RingBufferError> {
+        if data.len() != self.inner.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        let (latency, drop) = {
+            let mut rng = self.rng.lock().unwrap();
+            let latency = self.config.max_write_latency.mul_f32(rng.next_f32());
+            (latency, rng.next_f32() < self.config.drop_rate)
+        };
+
+        if !latency.is_zero() {
+            std::thread::sleep(latency);
+        }
+
+        if drop {
+            self.dropped.fetch_add(1, Ordering::SeqCst);
+            return Ok(());
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.push_back(data);
+        while pending.len() > self.config.visibility_delay {
+            if let Some(block) = pending.pop_front() {
+                self.inner.write(block)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publishes every block still held back by `visibility_delay`.
+    pub fn flush(&self) -> Result<(), RingBufferError> {
+        let mut pending = self.pending.lock().unwrap();
+        while let Some(block) = pending.pop_front() {
+            self.inner.write(block)?;
+        }
+
+        Ok(())
+    }
+
+    /// Number of writes dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes `blocks` numbered blocks, reading each one back as soon as it's
+    // visible, and returns what the consumer saw.
+    fn run(config: JitterConfig, blocks: u64) -> (Vec<u64>, u64) {
+        let ring = JitteryRingBuffer::new(RingBuffer::new(2, 4, 1000), config);
+        let mut seen = Vec::new();
+        let mut out = [0.0; 2];
+        for n in 0..blocks {
+            ring.write(vec![n as f32; 2]).unwrap();
+            while ring.inner().read_next_into(&mut out).unwrap() {
+                seen.push(out[0] as u64);
+            }
+        }
+
+        (seen, ring.dropped())
+    }
+
+    #[test]
+    fn seeded_drops_are_reproducible_and_near_the_rate() {
+        let config = JitterConfig {
+            drop_rate: 0.1,
+            seed: 42,
+            ..JitterConfig::default()
+        };
+        let (seen, dropped) = run(config.clone(), 1000);
+
+        assert_eq!(seen.len() as u64, 1000 - dropped);
+        assert!((70..=130).contains(&dropped), "dropped {dropped} of 1000");
+        assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(run(config, 1000), (seen, dropped));
+    }
+}
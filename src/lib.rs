@@ -1,11 +1,77 @@
 use std::error::Error;
 use std::fmt;
-use std::sync::atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::ops::{Deref, DerefMut};
+// Under loom the ring's own atomics are swapped for loom's so that
+// tests/loom.rs can model the writer and reader racing.
+#[cfg(loom)]
+use loom::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU32, AtomicU64, AtomicU8, Ordering};
+#[cfg(not(loom))]
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
+mod clock;
+#[cfg(feature = "wasm")]
+pub use clock::PerformanceClock;
+pub use clock::{Clock, ManualClock, SystemClock};
+
+#[cfg(feature = "num-complex")]
+mod complex;
+
+mod asrc;
+pub use asrc::{AsrcReader, CatchupStrategy};
+
+mod driver;
+pub use driver::{DriverHandle, FillStatus, ProducerDriver};
+
+mod framing;
+pub use framing::{FRAME_HEADER_LEN, FRAME_MAGIC};
+
+mod health;
+pub use health::{Health, HealthReport, HealthThreshold};
+
+mod integer;
+pub use integer::RoundingMode;
+
+mod invariants;
+pub use invariants::InvariantViolation;
+
+mod metering;
+
+mod sample_ring;
+pub use sample_ring::SampleRing;
+
+mod threads;
+
+mod waiter;
+use waiter::Waiter;
+
+#[cfg(feature = "symphonia")]
+mod symphonia;
+#[cfg(feature = "wasm-bindgen")]
+mod wasm;
+#[cfg(feature = "wasm-bindgen")]
+pub use wasm::WasmRingBuffer;
+
+#[cfg(any(test, feature = "test-util"))]
+mod jitter;
+#[cfg(any(test, feature = "test-util"))]
+pub use jitter::{JitterConfig, JitteryRingBuffer};
+
+#[cfg(test)]
+mod tests;
+
 #[derive(Debug)]
 pub enum RingBufferError {
     DataSizeMismatch,
+    SequenceNotResident,
+    Closed,
+    MemoryBudgetExceeded { requested: usize, budget: usize },
+    ChannelMismatch,
+    FrameMismatch,
+    TimedOut,
+    SelfTestFailed,
 }
 
 impl fmt::Display for RingBufferError {
@@ -14,40 +80,555 @@ impl fmt::Display for RingBufferError {
             RingBufferError::DataSizeMismatch => {
                 write!(f, "The size of data provided does not match buffer size")
             }
+            RingBufferError::SequenceNotResident => {
+                write!(f, "The requested sequence is not resident in the ring")
+            }
+            RingBufferError::Closed => write!(f, "The ring buffer has been closed"),
+            RingBufferError::MemoryBudgetExceeded { requested, budget } => write!(
+                f,
+                "The ring buffer needs {} bytes of sample storage, over the budget of {} bytes",
+                requested, budget
+            ),
+            RingBufferError::ChannelMismatch => {
+                write!(f, "The operation does not support this channel count")
+            }
+            RingBufferError::FrameMismatch => {
+                write!(f, "The frame header does not match this ring buffer")
+            }
+            RingBufferError::TimedOut => write!(f, "Timed out waiting on the ring buffer"),
+            RingBufferError::SelfTestFailed => {
+                write!(
+                    f,
+                    "Data read back from the ring did not match what was written"
+                )
+            }
         }
     }
 }
 
 impl Error for RingBufferError {}
 
+/// What the next call to [`RingBuffer::advance`] would do to the read cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdvanceDecision {
+    /// Keep serving the current block; the block period hasn't elapsed.
+    Hold,
+    /// The block period has elapsed but there is no new block, so the
+    /// current one is served again.
+    Starved,
+    /// Move the cursor forward by one block.
+    Advance,
+    /// The reader is too far behind and jumps to the newest block. `paced`
+    /// is true when a block period had also elapsed.
+    CatchUp { paced: bool },
+}
+
+/// How a paced reader that has fallen behind gets back to the writer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CatchUp {
+    /// Jump straight to the newest block.
+    Snap,
+    /// While more than `target` blocks are unread, shorten the block period
+    /// by up to `max_rate` so the backlog plays out fast instead of being
+    /// skipped. The rate ramps up and back down over a few blocks.
+    SpeedUp { max_rate: f64, target: u64 },
+}
+
+/// How paced reads decide when to move on to the next block. Switch at
+/// runtime with [`RingBuffer::set_pacing_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacingMode {
+    /// Advance once per block period of the ring's clock, for live output.
+    WallClock,
+    /// Advance on every read that has an unread block to move to, for
+    /// exporting faster than real time. Nothing is skipped to catch up.
+    Unpaced,
+}
+
+/// How [`RingBuffer::read_with`] hands a block to its callback. Every
+/// strategy delivers the same data; they differ only in cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadStrategy {
+    /// Copy the block into a scratch buffer owned by the reader. Cheapest
+    /// for small blocks.
+    #[default]
+    Copy,
+    /// Lend out the slot itself, pinned so the writer can't reuse it while
+    /// the callback runs. Saves the copy, but costs a fence on every read
+    /// and every write.
+    Borrow,
+    /// Borrow when `buffer_size` is at least `threshold` samples, copy
+    /// otherwise.
+    Auto { threshold: usize },
+}
+
+/// What a call to [`RingBuffer::read_into`] delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadOutcome {
+    /// Sequence of the block copied out, `None` when silence was served
+    /// because nothing had been claimed yet, or the current block was
+    /// skipped without being played or has been overwritten.
+    pub seq: Option<u64>,
+    /// The cursor advanced to a new block.
+    pub fresh: bool,
+    /// The block is a gap from [`RingBuffer::write_gap`], not audio.
+    pub intentional: bool,
+    /// The block period elapsed with no new block to move to.
+    pub underrun: bool,
+    /// The output was generated by the underrun fill callback.
+    pub synthetic: bool,
+    /// The block to serve had already been overwritten by the writer, so
+    /// silence (or the underrun fill) was served in its place.
+    pub overwritten: bool,
+}
+
+// Settings from RingBuffer::with_agc.
+#[derive(Debug, Clone, Copy)]
+struct Agc {
+    target_rms: f32,
+    attack: Duration,
+    release: Duration,
+}
+
+type UnderrunFill = Box<dyn FnMut(&mut [f32]) + Send>;
+type OverflowHandler = Box<dyn Fn(&[f32]) + Send + Sync>;
+
+// Slots are AtomicPtrs, which need a thin pointer, so a block is a boxed Vec.
+// Keeping the Box around between writes is what makes recycling allocation
+// free.
+#[allow(clippy::box_collection)]
+type Block = Box<Vec<f32>>;
+
+// Memory ordering
+//
+// The writer fills a block, swaps it into its slot with Release, and then
+// bumps total_writes with Release. A reader loads total_writes with Acquire
+// before picking a sequence, so the slot pointer and per-slot metadata
+// (written_at, gaps) for every seq < total_writes are visible to it, and
+// loads the slot pointer with Acquire, which pairs with the swap and makes
+// the block's samples visible.
+//
+// total_reads is stored only by the reader, with Release, after it is done
+// with the previous block. free_capacity loads it with Acquire, so a writer
+// that waited for capacity only reuses memory the reader has finished with.
+//
+// A reader never touches a block it hasn't pinned. It claims one of the
+// hazards, publishes the block it is about to copy or lend there and then
+// rechecks the slot, while the writer checks every hazard after swapping a
+// block out. A SeqCst fence on each side between the two means at least one
+// of them sees the other: either the reader finds the slot changed and
+// tries again, or the writer finds the block pinned and parks it in retired
+// instead of recycling it. The reader stores null in its hazard with
+// Release once it is done with a block, so a writer that loads a different
+// value with Acquire may reuse it.
+//
+// A pinned block is never mutated or freed, but it may already belong to a
+// later write than the one the reader wanted: the slot is swapped before
+// total_writes moves. So the writer bumps writes_begun and issues a Release
+// fence before touching a slot or its gap flag, and a reader that is done
+// loading issues an Acquire fence and checks writes_begun to tell whether
+// it got the block it asked for.
+//
+// closed and pause_state are Release/Acquire so that whatever was written before
+// them is visible once they are seen. Everything else (pacing state,
+// statistics, delivered marks) is Relaxed: it is either reader-only or a
+// metric where a momentarily stale value is harmless.
 pub struct RingBuffer {
     buffers: Vec<AtomicPtr<Vec<f32>>>,
-    start_time: Instant,
+    spare: AtomicPtr<Vec<f32>>,
+    hazards: [AtomicPtr<Vec<f32>>; HAZARD_SLOTS],
+    retired: Mutex<Vec<Block>>,
+    read_scratch: Mutex<Vec<f32>>,
+    capacity_waiter: Waiter,
+    writes_waiter: Waiter,
+    catch_up_waiter: Waiter,
+    delivered: Vec<AtomicU64>,
+    written_at: Vec<AtomicU64>,
+    write_times: [AtomicU64; WRITE_RATE_WINDOW as usize],
+    gaps: Vec<AtomicBool>,
+    clock: Arc<dyn Clock>,
     last_read: AtomicU64,
-    total_writes: AtomicUsize,
-    total_reads: AtomicUsize,
+    total_writes: AtomicU64,
+    writes_begun: AtomicU64,
+    total_reads: AtomicU64,
+    last_read_fresh: AtomicBool,
+    last_read_underrun: AtomicBool,
+    health_watch: Mutex<Option<Arc<health::HealthWatch>>>,
+    last_health: AtomicU8,
+    aged_out: AtomicU64,
+    skipped_through: AtomicU64,
+    underruns: AtomicU64,
+    catch_ups: AtomicU64,
+    speed_ups: AtomicU64,
+    peak_lag: AtomicU64,
+    latency_ema: AtomicU64,
+    e2e_latency: AtomicU64,
+    synthetic_fills: AtomicU64,
+    underrun_fill: Mutex<Option<UnderrunFill>>,
+    overflow_handler: Mutex<Option<OverflowHandler>>,
+    closed: AtomicBool,
+    pause_state: AtomicU64,
+    pacing_mode: AtomicU8,
     buffer_size: usize,
     ring_buffer_size: usize,
     sample_rate: f32,
+    channels: usize,
+    auto_catchup: bool,
+    catch_up: CatchUp,
+    catch_up_rate: AtomicU64,
+    clear_on_read: bool,
+    read_strategy: ReadStrategy,
+    max_age: Option<Duration>,
+    soft_clip: Option<f32>,
+    agc: Option<Agc>,
+    agc_gain: AtomicU32,
+    correlation_meter: bool,
+    panic_on_overrun: bool,
+    correlation: AtomicU32,
+    #[cfg(feature = "metrics")]
+    allocations: AtomicU64,
+    thread_check: threads::ThreadCheck,
 }
 
+/// Sample storage [`RingBuffer::try_new`] allows before refusing, 256 MiB.
+pub const DEFAULT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Block size used by [`RingBuffer::default`].
+pub const DEFAULT_BUFFER_SIZE: usize = 512;
+
+/// Number of blocks used by [`RingBuffer::default`].
+pub const DEFAULT_RING_BUFFER_SIZE: usize = 8;
+
+/// Sample rate used by [`RingBuffer::default`].
+pub const DEFAULT_SAMPLE_RATE: usize = 48_000;
+
+// Number of recent writes measured_write_rate averages over, independent of
+// the ring size.
+const WRITE_RATE_WINDOW: u64 = 8;
+
+// Tag bits in pause_state: paused, or pause() is reading the clock.
+const PAUSED: u64 = 1 << 63;
+const PAUSING: u64 = 1 << 62;
+
+// Readers that can hold a block at once: the audio reader plus peekers such
+// as read_latest or the meters, possibly on other threads.
+const HAZARD_SLOTS: usize = 4;
+
+// Marks a hazard slot as claimed by a reader that hasn't picked a block yet.
+// Never the address of a real block.
+const HAZARD_RESERVED: *mut Vec<f32> = std::ptr::NonNull::dangling().as_ptr();
+
 impl RingBuffer {
+    /// Like [`new`](Self::new), but refuses configurations whose sample
+    /// storage exceeds [`DEFAULT_MEMORY_BUDGET`].
+    pub fn try_new(
+        buffer_size: usize,
+        ring_buffer_size: usize,
+        sample_rate: usize,
+    ) -> Result<Self, RingBufferError> {
+        Self::try_new_with_budget(
+            buffer_size,
+            ring_buffer_size,
+            sample_rate,
+            DEFAULT_MEMORY_BUDGET,
+        )
+    }
+
+    /// Like [`new`](Self::new), but refuses configurations needing more than
+    /// `budget` bytes of sample storage.
+    pub fn try_new_with_budget(
+        buffer_size: usize,
+        ring_buffer_size: usize,
+        sample_rate: usize,
+        budget: usize,
+    ) -> Result<Self, RingBufferError> {
+        let requested = buffer_size
+            .checked_mul(ring_buffer_size)
+            .and_then(|samples| samples.checked_mul(std::mem::size_of::<f32>()))
+            .unwrap_or(usize::MAX);
+        if requested > budget {
+            return Err(RingBufferError::MemoryBudgetExceeded { requested, budget });
+        }
+
+        Ok(Self::new(buffer_size, ring_buffer_size, sample_rate))
+    }
+
     pub fn new(buffer_size: usize, ring_buffer_size: usize, sample_rate: usize) -> Self {
-        let now = Instant::now();
+        Self::new_with_clock(
+            buffer_size,
+            ring_buffer_size,
+            sample_rate,
+            SystemClock::new(),
+        )
+    }
+
+    /// Like [`new`](Self::new), pacing reads against `clock` instead of the
+    /// system clock.
+    pub fn new_with_clock(
+        buffer_size: usize,
+        ring_buffer_size: usize,
+        sample_rate: usize,
+        clock: impl Clock + 'static,
+    ) -> Self {
+        Self::construct(
+            buffer_size,
+            ring_buffer_size,
+            sample_rate,
+            0.0,
+            Arc::new(clock),
+        )
+    }
+
+    /// Like [`new`](Self::new), but every slot starts out holding `fill`
+    /// instead of silence, e.g. `f32::NAN` to make never-written slots easy
+    /// to spot while debugging.
+    pub fn new_with_fill(
+        buffer_size: usize,
+        ring_buffer_size: usize,
+        sample_rate: usize,
+        fill: f32,
+    ) -> Self {
+        Self::construct(
+            buffer_size,
+            ring_buffer_size,
+            sample_rate,
+            fill,
+            Arc::new(SystemClock::new()),
+        )
+    }
 
+    fn construct(
+        buffer_size: usize,
+        ring_buffer_size: usize,
+        sample_rate: usize,
+        fill: f32,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
         let buffers = (0..ring_buffer_size)
-            .map(|_| AtomicPtr::new(Box::into_raw(Box::new(vec![0.0; buffer_size]))))
+            .map(|_| AtomicPtr::new(Box::into_raw(Box::new(vec![fill; buffer_size]))))
+            .collect();
+        let delivered = (0..ring_buffer_size).map(|_| AtomicU64::new(0)).collect();
+        let written_at = (0..ring_buffer_size).map(|_| AtomicU64::new(0)).collect();
+        let gaps = (0..ring_buffer_size)
+            .map(|_| AtomicBool::new(false))
             .collect();
 
         Self {
             buffers,
-            start_time: now,
-            last_read: AtomicU64::new(Self::to_nanos(&now.elapsed())),
-            total_writes: AtomicUsize::new(0),
-            total_reads: AtomicUsize::new(0),
+            spare: AtomicPtr::new(std::ptr::null_mut()),
+            hazards: std::array::from_fn(|_| AtomicPtr::new(std::ptr::null_mut())),
+            retired: Mutex::new(Vec::with_capacity(HAZARD_SLOTS)),
+            read_scratch: Mutex::new(vec![0.0; buffer_size]),
+            capacity_waiter: Waiter::default(),
+            writes_waiter: Waiter::default(),
+            catch_up_waiter: Waiter::default(),
+            delivered,
+            written_at,
+            write_times: Default::default(),
+            gaps,
+            last_read: AtomicU64::new(Self::to_nanos(&clock.now())),
+            clock,
+            total_writes: AtomicU64::new(0),
+            writes_begun: AtomicU64::new(0),
+            total_reads: AtomicU64::new(0),
+            last_read_fresh: AtomicBool::new(false),
+            last_read_underrun: AtomicBool::new(false),
+            health_watch: Mutex::new(None),
+            last_health: AtomicU8::new(Health::Ok as u8),
+            aged_out: AtomicU64::new(0),
+            skipped_through: AtomicU64::new(0),
+            underruns: AtomicU64::new(0),
+            catch_ups: AtomicU64::new(0),
+            speed_ups: AtomicU64::new(0),
+            peak_lag: AtomicU64::new(0),
+            latency_ema: AtomicU64::new(f64::NAN.to_bits()),
+            e2e_latency: AtomicU64::new(f64::NAN.to_bits()),
+            synthetic_fills: AtomicU64::new(0),
+            underrun_fill: Mutex::new(None),
+            overflow_handler: Mutex::new(None),
+            closed: AtomicBool::new(false),
+            pause_state: AtomicU64::new(0),
+            pacing_mode: AtomicU8::new(PacingMode::WallClock as u8),
             buffer_size,
             ring_buffer_size,
             sample_rate: sample_rate as f32,
+            channels: 1,
+            auto_catchup: true,
+            catch_up: CatchUp::Snap,
+            catch_up_rate: AtomicU64::new(1.0f64.to_bits()),
+            clear_on_read: false,
+            read_strategy: ReadStrategy::Copy,
+            max_age: None,
+            soft_clip: None,
+            agc: None,
+            agc_gain: AtomicU32::new(1.0f32.to_bits()),
+            correlation_meter: false,
+            panic_on_overrun: false,
+            correlation: AtomicU32::new(0.0f32.to_bits()),
+            #[cfg(feature = "metrics")]
+            allocations: AtomicU64::new(2 * ring_buffer_size as u64),
+            thread_check: threads::ThreadCheck::default(),
+        }
+    }
+
+    /// Sets the number of interleaved channels in each block. Panics if
+    /// `buffer_size` is not a whole number of frames.
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        assert!(
+            channels > 0 && self.buffer_size.is_multiple_of(channels),
+            "buffer_size must be a multiple of channels"
+        );
+        self.channels = channels;
+        self
+    }
+
+    /// Enables or disables jumping to the newest block when the reader falls
+    /// too far behind. With it disabled the reader advances at most one block
+    /// per read, only skipping blocks the writer has already overwritten.
+    pub fn with_auto_catchup(mut self, auto_catchup: bool) -> Self {
+        self.auto_catchup = auto_catchup;
+        self
+    }
+
+    /// Chooses how a paced reader that has fallen behind gets back to the
+    /// writer. Defaults to [`CatchUp::Snap`], which only applies with
+    /// [`with_auto_catchup`](Self::with_auto_catchup) on;
+    /// [`CatchUp::SpeedUp`] never skips blocks and works either way.
+    pub fn with_catch_up(mut self, catch_up: CatchUp) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    /// Once a block has been delivered to a reader, any later copy of it
+    /// yields silence instead of replaying it. Slots are only marked, not
+    /// zeroed, so the cost is one atomic store per copy.
+    pub fn with_clear_on_read(mut self, clear_on_read: bool) -> Self {
+        self.clear_on_read = clear_on_read;
+        self
+    }
+
+    /// Chooses how [`read_with`](Self::read_with) hands blocks to its
+    /// callback. Defaults to [`ReadStrategy::Copy`].
+    pub fn with_read_strategy(mut self, read_strategy: ReadStrategy) -> Self {
+        self.read_strategy = read_strategy;
+        self
+    }
+
+    pub fn read_strategy(&self) -> ReadStrategy {
+        self.read_strategy
+    }
+
+    // Whether the read strategy lends out slots for this block size.
+    fn borrows(&self) -> bool {
+        match self.read_strategy {
+            ReadStrategy::Copy => false,
+            ReadStrategy::Borrow => true,
+            ReadStrategy::Auto { threshold } => self.buffer_size >= threshold,
+        }
+    }
+
+    /// Unread blocks written longer than `max_age` ago are skipped before
+    /// each read, so the reader never plays audio that is too late.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Paced reads pass samples within `threshold` untouched and bend
+    /// larger ones towards full scale instead of letting them clip:
+    /// `|y| = t + (1 - t) * tanh((|x| - t) / (1 - t))` for `|x| > t`. The
+    /// curve meets the identity with matching slope at `t` and levels off
+    /// at 1. Panics unless `0 <= threshold < 1`.
+    pub fn with_soft_clip(mut self, threshold: f32) -> Self {
+        assert!(
+            (0.0..1.0).contains(&threshold),
+            "soft clip threshold must be in [0, 1)"
+        );
+        self.soft_clip = Some(threshold);
+        self
+    }
+
+    /// Paced reads apply a slowly adapting gain that steers each block's
+    /// RMS towards `target_rms`. The gain falls with time constant `attack`
+    /// when blocks are too loud and rises with `release` when they are too
+    /// quiet, converted to per-block steps using the sample rate, and is
+    /// ramped across each block so changes don't click. Blocks of near
+    /// silence hold the gain rather than boosting the noise floor. Runs
+    /// before [`with_soft_clip`](Self::with_soft_clip) when both are set.
+    pub fn with_agc(mut self, target_rms: f32, attack: Duration, release: Duration) -> Self {
+        self.agc = Some(Agc {
+            target_rms,
+            attack,
+            release,
+        });
+        self
+    }
+
+    /// Measures the L/R correlation of every stereo block as it is written,
+    /// for [`stereo_correlation`](Self::stereo_correlation). Costs one pass
+    /// over each block on the writer thread.
+    pub fn with_correlation_meter(mut self, enabled: bool) -> Self {
+        self.correlation_meter = enabled;
+        self
+    }
+
+    /// Makes any write that would overwrite a block the reader never
+    /// claimed panic instead of silently dropping it, so soak tests fail
+    /// loudly on data loss. Only honoured in debug builds.
+    pub fn with_panic_on_overrun(mut self, panic_on_overrun: bool) -> Self {
+        self.panic_on_overrun = panic_on_overrun;
+        self
+    }
+
+    /// The gain the AGC applied at the end of the last paced read, 1.0 if
+    /// [`with_agc`](Self::with_agc) isn't set.
+    pub fn agc_gain(&self) -> f32 {
+        f32::from_bits(self.agc_gain.load(Ordering::Relaxed))
+    }
+
+    fn apply_agc(&self, out: &mut [f32]) {
+        let Some(agc) = self.agc else {
+            return;
+        };
+        if out.is_empty() {
+            return;
+        }
+
+        let rms = (out.iter().map(|s| s * s).sum::<f32>() / out.len() as f32).sqrt();
+        let gain = self.agc_gain();
+        let mut next = gain;
+        if rms > 1e-4 {
+            let wanted = agc.target_rms / rms;
+            let time_constant = if wanted < gain {
+                agc.attack
+            } else {
+                agc.release
+            };
+            // one-pole smoothing, stepped once per block
+            let block_secs = self.frames_per_block() as f32 / self.sample_rate;
+            let coefficient = 1.0 - (-block_secs / time_constant.as_secs_f32()).exp();
+            next = gain + (wanted - gain) * coefficient;
+        }
+
+        let step = (next - gain) / out.len() as f32;
+        for (i, sample) in out.iter_mut().enumerate() {
+            *sample *= gain + step * (i + 1) as f32;
+        }
+        self.agc_gain.store(next.to_bits(), Ordering::Relaxed);
+    }
+
+    fn soft_clip(&self, out: &mut [f32]) {
+        let Some(threshold) = self.soft_clip else {
+            return;
+        };
+
+        let knee = 1.0 - threshold;
+        for sample in out.iter_mut() {
+            let magnitude = sample.abs();
+            if magnitude > threshold {
+                let bent = threshold + knee * ((magnitude - threshold) / knee).tanh();
+                *sample = bent.copysign(*sample);
+            }
         }
     }
 
@@ -62,54 +643,1688 @@ impl RingBuffer {
         )
     }
 
+    // Pacing time: the clock minus any time spent paused. pause_state is
+    // one word so a concurrent pause or resume can't be seen half done:
+    // while running it holds the total time spent paused, while paused the
+    // pacing time it stopped at, tagged with PAUSED. Rereading it after the
+    // clock means the offset applied is the one in force when the clock was
+    // read, and pause() only reads the clock once it has tagged the word
+    // PAUSING, so pacing time never goes backwards.
+    fn now_nanos(&self) -> u64 {
+        loop {
+            let state = self.pause_state.load(Ordering::Acquire);
+            if state & PAUSED != 0 {
+                return state & !PAUSED;
+            }
+            if state & PAUSING == 0 {
+                let now = Self::to_nanos(&self.clock.now());
+                if self.pause_state.load(Ordering::Acquire) == state {
+                    return now.saturating_sub(state);
+                }
+            }
+            std::hint::spin_loop();
+        }
+    }
+
+    /// Stops the pacing clock. Reads keep serving the current block without
+    /// advancing, and paused time doesn't count towards pacing or block age.
+    pub fn pause(&self) {
+        let state = self.pause_state.load(Ordering::Acquire);
+        if state & (PAUSED | PAUSING) == 0
+            && self
+                .pause_state
+                .compare_exchange(state, state | PAUSING, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            let stopped_at = Self::to_nanos(&self.clock.now()).saturating_sub(state);
+            self.pause_state
+                .store(stopped_at | PAUSED, Ordering::Release);
+        }
+    }
+
+    pub fn resume(&self) {
+        loop {
+            let state = self.pause_state.load(Ordering::Acquire);
+            if state & PAUSING != 0 {
+                std::hint::spin_loop();
+                continue;
+            }
+            if state & PAUSED == 0 {
+                return;
+            }
+            let paused_total = Self::to_nanos(&self.clock.now()).saturating_sub(state & !PAUSED);
+            if self
+                .pause_state
+                .compare_exchange(state, paused_total, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause_state.load(Ordering::Acquire) & (PAUSED | PAUSING) != 0
+    }
+
+    fn slot_index(&self, seq: u64) -> usize {
+        (seq % self.ring_buffer_size as u64) as usize
+    }
+
+    fn slot(&self, seq: u64) -> &AtomicPtr<Vec<f32>> {
+        &self.buffers[self.slot_index(seq)]
+    }
+
     pub fn write(&self, data: Vec<f32>) -> Result<(), RingBufferError> {
         if data.len() != self.buffer_size {
             return Err(RingBufferError::DataSizeMismatch);
         }
+        if self.is_closed() {
+            return Err(RingBufferError::Closed);
+        }
+
+        self.count_allocations(1);
+        self.publish(Box::new(data));
+
+        Ok(())
+    }
+
+    /// [`write`](Self::write) without the length check, for hot paths that
+    /// already guarantee `data.len() == buffer_size`. Debug builds assert it;
+    /// in release a wrong-sized block is stored as is and any later
+    /// full-block copy of it panics.
+    pub fn write_unchecked(&self, data: Vec<f32>) -> Result<(), RingBufferError> {
+        debug_assert_eq!(data.len(), self.buffer_size);
+        if self.is_closed() {
+            return Err(RingBufferError::Closed);
+        }
+
+        self.count_allocations(1);
+        self.publish(Box::new(data));
+
+        Ok(())
+    }
+
+    /// Copies `data` into a recycled block and publishes it. Once the ring
+    /// has wrapped this doesn't allocate, unlike [`write`](Self::write),
+    /// which boxes the caller's `Vec`.
+    pub fn write_slice(&self, data: &[f32]) -> Result<(), RingBufferError> {
+        if data.len() != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+        if self.is_closed() {
+            return Err(RingBufferError::Closed);
+        }
+
+        let mut block = self.take_spare();
+        block.clear();
+        block.extend_from_slice(data);
+        self.publish(block);
+
+        Ok(())
+    }
+
+    /// Splits `data` into consecutive `buffer_size` blocks and writes each
+    /// one. Returns the number of samples accepted; a trailing partial block
+    /// is left for the caller to resubmit with more data.
+    pub fn write_chunked(&self, data: &[f32]) -> Result<usize, RingBufferError> {
+        if self.buffer_size == 0 {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+        if self.is_closed() {
+            return Err(RingBufferError::Closed);
+        }
+
+        let mut accepted = 0;
+        for chunk in data.chunks_exact(self.buffer_size) {
+            let mut block = self.take_spare();
+            block.clear();
+            block.extend_from_slice(chunk);
+            self.publish(block);
+            accepted += chunk.len();
+        }
+
+        Ok(accepted)
+    }
 
-        let total_writes = self.total_writes.load(Ordering::SeqCst);
-        let write_index = total_writes % self.ring_buffer_size;
-        // println!("write: {:?}", total_writes);
+    /// Marks the end of the stream. Later writes fail with `Closed`; blocks
+    /// already written can still be read.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify_capacity();
+        self.notify_writes();
+        self.notify_catch_up();
+    }
 
-        let old_data =
-            self.buffers[write_index].swap(Box::into_raw(Box::new(data)), Ordering::SeqCst);
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
 
-        unsafe {
-            _ = Box::from_raw(old_data);
-        } // safely drop the old data
+    /// Writes `duration_in_buffers` markers for intentional silence. They
+    /// take no sample storage and are paced like real blocks, but read back
+    /// as silence flagged `intentional` rather than as underruns.
+    pub fn write_gap(&self, duration_in_buffers: usize) -> Result<(), RingBufferError> {
+        if self.is_closed() {
+            return Err(RingBufferError::Closed);
+        }
 
-        self.total_writes.fetch_add(1, Ordering::SeqCst);
+        self.thread_check.note_writer();
+        for _ in 0..duration_in_buffers {
+            let total_writes = self.total_writes.load(Ordering::Relaxed);
+            let index = self.slot_index(total_writes);
+            self.spill_unread(total_writes);
+            self.begin_write(total_writes);
+            self.stamp_write(total_writes);
+            self.gaps[index].store(true, Ordering::Relaxed);
+            self.total_writes.fetch_add(1, Ordering::Release);
+        }
+        self.peak_lag.fetch_max(self.available(), Ordering::Relaxed);
+        self.notify_writes();
+        self.check_health();
 
         Ok(())
     }
 
-    pub fn read(&self) -> Vec<f32> {
-        let total_writes = self.total_writes.load(Ordering::SeqCst);
-        let total_reads = self.total_reads.load(Ordering::SeqCst);
-        // println!("read: {:?}", total_reads);
+    // Announces write `seq` before its slot or gap flag changes, so that a
+    // reader that sees either change also sees it was overwritten.
+    fn begin_write(&self, seq: u64) {
+        self.writes_begun.store(seq + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+    }
 
-        // Get the elapsed time in seconds as a float
-        let elapsed_time = Self::from_nanos(
-            self.start_time.elapsed().as_nanos() - (self.last_read.load(Ordering::SeqCst) as u128),
-        )
-        .as_secs_f32();
+    // Records when write `seq` happened, for max_age and the write rate.
+    fn stamp_write(&self, seq: u64) {
+        let now = self.now_nanos();
+        self.written_at[self.slot_index(seq)].store(now, Ordering::Relaxed);
+        self.write_times[(seq % WRITE_RATE_WINDOW) as usize].store(now, Ordering::Relaxed);
+    }
+
+    fn publish(&self, data: Block) {
+        self.thread_check.note_writer();
+        let total_writes = self.total_writes.load(Ordering::Relaxed);
+        let index = self.slot_index(total_writes);
+        self.spill_unread(total_writes);
+        self.begin_write(total_writes);
+
+        self.stamp_write(total_writes);
+        self.gaps[index].store(false, Ordering::Relaxed);
+        self.meter_correlation(&data);
+
+        let old_data = self
+            .slot(total_writes)
+            .swap(Box::into_raw(data), Ordering::Release);
+
+        // keep the old block around for the next write_guard, unless a
+        // reader still has it pinned
+        let old_data = unsafe { Box::from_raw(old_data) };
+        if self.is_lent(&old_data) {
+            self.retire(old_data);
+        } else {
+            self.recycle(old_data);
+        }
+
+        self.total_writes.fetch_add(1, Ordering::Release);
+        self.peak_lag.fetch_max(self.available(), Ordering::Relaxed);
+        self.notify_writes();
+        self.check_health();
+        self.debug_assert_invariants();
+    }
+
+    // Hands the block about to be replaced by write `seq` to the overflow
+    // handler if the reader never claimed it. Gaps hold no audio to spill.
+    fn spill_unread(&self, seq: u64) {
+        let Some(evicted) = seq.checked_sub(self.ring_buffer_size as u64) else {
+            return;
+        };
+        let total_reads = self.total_reads.load(Ordering::Acquire);
+        if evicted < total_reads {
+            return;
+        }
+        if cfg!(debug_assertions) && self.panic_on_overrun {
+            panic!(
+                "write {} overwrites unread block {}: the reader has only claimed {} of a {}-block ring",
+                seq, evicted, total_reads, self.ring_buffer_size
+            );
+        }
+        #[cfg(feature = "tracing")]
+        tracing::warn!(seq, evicted, total_reads, "ring buffer overrun");
+        if self.is_gap(evicted) {
+            return;
+        }
+
+        // try_lock: the writer must never block on set_overflow_handler
+        if let Ok(handler) = self.overflow_handler.try_lock() {
+            if let Some(handler) = handler.as_ref() {
+                handler(unsafe { &*self.slot(evicted).load(Ordering::Relaxed) });
+            }
+        }
+    }
+
+    fn recycle(&self, data: Block) {
+        let old_spare = self.spare.swap(Box::into_raw(data), Ordering::AcqRel);
+        if !old_spare.is_null() {
+            unsafe {
+                _ = Box::from_raw(old_spare);
+            }
+        }
+    }
+
+    // Whether a reader has pinned `data`, which the writer has just swapped
+    // out of its slot.
+    fn is_lent(&self, data: &Vec<f32>) -> bool {
+        fence(Ordering::SeqCst);
+        self.is_pinned(data)
+    }
+
+    fn is_pinned(&self, data: &Vec<f32>) -> bool {
+        self.hazards
+            .iter()
+            .any(|hazard| std::ptr::eq(hazard.load(Ordering::Acquire), data))
+    }
+
+    // Parks a block a reader still has pinned. Whatever was parked before
+    // and has since been let go of is recycled.
+    fn retire(&self, data: Block) {
+        while let Some(free) = self.reclaim_retired() {
+            self.recycle(free);
+        }
+        // only the writer locks retired, so this never waits
+        self.retired.lock().unwrap().push(data);
+    }
+
+    // A retired block no reader has pinned any more.
+    fn reclaim_retired(&self) -> Option<Block> {
+        let mut retired = self.retired.lock().unwrap();
+        let index = retired.iter().position(|data| !self.is_pinned(data))?;
+        Some(retired.swap_remove(index))
+    }
+
+    /// Hands out a `buffer_size` block to fill in place and publish with
+    /// [`WriteGuard::commit`]. The block is recycled from an earlier write
+    /// when possible, so its contents are unspecified.
+    pub fn write_guard(&self) -> WriteGuard<'_> {
+        let mut data = self.take_spare();
+        data.resize(self.buffer_size, 0.0);
+
+        WriteGuard {
+            ring: self,
+            data: Some(data),
+        }
+    }
+
+    /// Number of sample blocks and their boxes allocated over the buffer's
+    /// lifetime, including the initial slots. Stays flat once writes are
+    /// served from recycled blocks.
+    #[cfg(feature = "metrics")]
+    pub fn allocation_count(&self) -> u64 {
+        self.allocations.load(Ordering::Relaxed)
+    }
+
+    fn count_allocations(&self, _n: u64) {
+        #[cfg(feature = "metrics")]
+        self.allocations.fetch_add(_n, Ordering::Relaxed);
+    }
+
+    // The recycled block if there is one, otherwise a new empty one with
+    // room for a block.
+    fn take_spare(&self) -> Block {
+        let spare = self.spare.swap(std::ptr::null_mut(), Ordering::AcqRel);
+        if !spare.is_null() {
+            return unsafe { Box::from_raw(spare) };
+        }
+        if let Some(retired) = self.reclaim_retired() {
+            return retired;
+        }
+
+        self.count_allocations(2);
+        Box::new(Vec::with_capacity(self.buffer_size))
+    }
+
+    /// Number of blocks that can be written without overwriting unread
+    /// blocks or the block the reader is currently serving.
+    pub fn free_capacity(&self) -> u64 {
+        let in_use = self.available() + self.current_seq().is_some() as u64;
+        (self.ring_buffer_size as u64).saturating_sub(in_use)
+    }
+
+    /// Parks the calling thread until the reader frees capacity, `timeout`
+    /// passes, the ring is closed or the thread is unparked by someone else.
+    /// Returns whether there is free capacity.
+    pub fn wait_for_capacity(&self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        // parks at most once, so an unpark from outside (say
+        // DriverHandle::stop) returns control to the caller
+        let mut parked = false;
+        self.capacity_waiter.wait(deadline, || {
+            if parked || self.free_capacity() > 0 || self.is_closed() {
+                return Some(());
+            }
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                available = self.available(),
+                "ring buffer writer stalled on a full ring"
+            );
+            parked = true;
+            None
+        });
+
+        self.free_capacity() > 0
+    }
+
+    /// Parks the calling thread until `count` blocks have been written in
+    /// total. Errors with `Closed` if the ring is closed first and
+    /// `TimedOut` once `timeout` passes. Only one thread should wait at a
+    /// time.
+    pub fn wait_for_writes(
+        &self,
+        count: u64,
+        timeout: Option<Duration>,
+    ) -> Result<(), RingBufferError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self.writes_waiter
+            .wait(deadline, || {
+                if self.total_writes.load(Ordering::Acquire) >= count {
+                    Some(Ok(()))
+                } else if self.is_closed() {
+                    Some(Err(RingBufferError::Closed))
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(Err(RingBufferError::TimedOut))
+    }
+
+    /// Whether every slot has been written at least once since
+    /// construction.
+    pub fn is_fully_populated(&self) -> bool {
+        self.total_writes.load(Ordering::Acquire) >= self.ring_buffer_size as u64
+    }
+
+    /// Parks the calling thread until the ring has been written all the way
+    /// around once, for consumers that want a full warmup before starting.
+    /// Errors like [`wait_for_writes`](Self::wait_for_writes).
+    pub fn wait_until_full(&self, timeout: Option<Duration>) -> Result<(), RingBufferError> {
+        self.wait_for_writes(self.ring_buffer_size as u64, timeout)
+    }
+
+    fn notify_writes(&self) {
+        self.writes_waiter.notify();
+    }
+
+    fn notify_capacity(&self) {
+        self.capacity_waiter.notify();
+    }
+
+    /// Reports what [`advance`](Self::advance) would do right now, without
+    /// side effects.
+    pub fn should_advance(&self) -> AdvanceDecision {
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        let mut total_reads = self.total_reads.load(Ordering::Relaxed);
+
+        if self.is_paused() {
+            return AdvanceDecision::Hold;
+        }
+        if self.pacing_mode() == PacingMode::Unpaced {
+            return if total_reads < total_writes {
+                AdvanceDecision::Advance
+            } else {
+                AdvanceDecision::Hold
+            };
+        }
+
+        let due = self.elapsed_since_advance().as_secs_f32() >= self.advance_threshold();
+        let paced = due && total_reads < total_writes;
+        if paced {
+            total_reads += 1;
+        }
 
-        if total_reads < total_writes - 1
-            && elapsed_time >= self.buffer_size as f32 / self.sample_rate * 0.75
+        // total_reads counts claimed blocks, so the current block is
+        // total_reads - 1.
+        if self.auto_catchup
+            && self.catch_up == CatchUp::Snap
+            && total_reads > 11
+            && total_reads + 5 < total_writes
         {
-            self.total_reads.fetch_add(1, Ordering::SeqCst);
-            self.last_read
-                .store(Self::to_nanos(&self.start_time.elapsed()), Ordering::SeqCst);
+            AdvanceDecision::CatchUp { paced }
+        } else if paced {
+            AdvanceDecision::Advance
+        } else if due {
+            AdvanceDecision::Starved
+        } else {
+            AdvanceDecision::Hold
+        }
+    }
+
+    /// Switches how reads are paced, effective from the next read. The
+    /// pacing clock restarts and any catch-up speed-up is dropped, so going
+    /// back to `WallClock` resumes at one block per period rather than
+    /// bursting to make up for the time spent unpaced.
+    pub fn set_pacing_mode(&self, mode: PacingMode) {
+        if self.pacing_mode.swap(mode as u8, Ordering::AcqRel) != mode as u8 {
+            self.last_read.store(self.now_nanos(), Ordering::Relaxed);
+            self.catch_up_rate
+                .store(1.0f64.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn pacing_mode(&self) -> PacingMode {
+        if self.pacing_mode.load(Ordering::Acquire) == PacingMode::Unpaced as u8 {
+            PacingMode::Unpaced
+        } else {
+            PacingMode::WallClock
+        }
+    }
+
+    // Get the elapsed time since the last paced advance
+    fn elapsed_since_advance(&self) -> Duration {
+        Self::from_nanos(
+            self.now_nanos()
+                .saturating_sub(self.last_read.load(Ordering::Relaxed)) as u128,
+        )
+    }
+
+    // Seconds of audio in one block at the current catch-up rate.
+    fn block_period(&self) -> f32 {
+        self.frames_per_block() as f32 / self.sample_rate / self.catch_up_rate() as f32
+    }
+
+    // Seconds after the last advance at which the next one becomes due.
+    fn advance_threshold(&self) -> f32 {
+        self.block_period() * 0.75
+    }
+
+    /// Time left until a paced read may advance again; zero once it's due.
+    /// While paused this is a full threshold, since pacing time is stopped.
+    pub fn time_until_advance(&self) -> Duration {
+        let threshold = Duration::from_secs_f32(self.advance_threshold());
+        if self.is_paused() {
+            return threshold;
+        }
+
+        threshold.saturating_sub(self.elapsed_since_advance())
+    }
+
+    /// Runs a paced consumer loop on the calling thread until `stop` is set,
+    /// calling `f` with each fresh block and sleeping until the next advance
+    /// is due in between.
+    pub fn run_consumer(&self, mut f: impl FnMut(&[f32]), stop: Arc<AtomicBool>) {
+        let mut block = vec![0.0; self.buffer_size];
+        let idle = Duration::from_secs_f32(self.advance_threshold() / 4.0);
+
+        while !stop.load(Ordering::Relaxed) {
+            let fresh = matches!(self.read_into(&mut block), Ok(outcome) if outcome.fresh);
+            if fresh {
+                f(&block);
+            }
+
+            let wait = self.time_until_advance();
+            // due but nothing to read: don't spin while waiting for the writer
+            thread::sleep(if wait.is_zero() && !fresh { idle } else { wait });
         }
+    }
+
+    /// Commits the cursor move decided by [`should_advance`](Self::should_advance)
+    /// and returns the newly claimed sequence, or `None` if the cursor held.
+    ///
+    /// If the writer has lapped the cursor, the claim skips to the oldest
+    /// block still resident. A claimed sequence stays valid for
+    /// [`copy_seq_into`](Self::copy_seq_into) until the writer has written
+    /// `ring_buffer_size` further blocks and reuses its slot.
+    pub fn advance(&self) -> Option<u64> {
+        self.advance_with_decision().1
+    }
+
+    fn advance_with_decision(&self) -> (AdvanceDecision, Option<u64>) {
+        self.thread_check.note_reader();
+        self.evict_aged();
 
-        let total_reads = self.total_reads.load(Ordering::SeqCst);
-        if total_reads > 10 && total_reads < total_writes - 6 {
-            println!("caught up");
-            self.total_reads.store(total_writes, Ordering::SeqCst);
+        let decision = self.should_advance();
+        match decision {
+            AdvanceDecision::Hold => return (decision, None),
+            AdvanceDecision::Starved => {
+                self.underruns.fetch_add(1, Ordering::Relaxed);
+                #[cfg(feature = "tracing")]
+                tracing::trace!(underruns = self.underruns(), "ring buffer underrun");
+                return (decision, None);
+            }
+            _ => {}
         }
 
-        let read_index = total_reads % self.ring_buffer_size;
-        let data_ptr = self.buffers[read_index].load(Ordering::SeqCst);
-        unsafe { &*data_ptr }.clone()
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        let oldest = total_writes.saturating_sub(self.ring_buffer_size as u64);
+        let seq = match decision {
+            AdvanceDecision::CatchUp { .. } => total_writes - 1,
+            _ => self.total_reads.load(Ordering::Relaxed).max(oldest),
+        };
+
+        self.debug_assert_claim(seq, total_writes);
+        #[cfg(feature = "tracing")]
+        let lag = total_writes - self.total_reads.load(Ordering::Relaxed);
+        if let AdvanceDecision::Advance | AdvanceDecision::CatchUp { paced: true } = decision {
+            self.last_read.store(self.now_nanos(), Ordering::Relaxed);
+        }
+        self.advance_reads(seq, 1);
+        self.record_e2e_latency(seq);
+        if let AdvanceDecision::CatchUp { .. } = decision {
+            self.catch_ups.fetch_add(1, Ordering::Release);
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                lag,
+                catch_ups = self.catch_ups(),
+                seq,
+                "ring buffer reader caught up"
+            );
+            self.notify_catch_up();
+        }
+        self.update_catch_up_rate();
+
+        (decision, Some(seq))
+    }
+
+    fn update_catch_up_rate(&self) {
+        let CatchUp::SpeedUp { max_rate, target } = self.catch_up else {
+            return;
+        };
+
+        let max_rate = max_rate.max(1.0);
+        let desired = if self.available() > target {
+            max_rate
+        } else {
+            1.0
+        };
+        let current = self.catch_up_rate();
+        let mut rate = current + (desired - current) * 0.25;
+        if (desired - rate).abs() < 1e-3 {
+            rate = desired;
+        }
+        let rate = rate.clamp(1.0, max_rate);
+
+        if current == 1.0 && rate > 1.0 {
+            self.speed_ups.fetch_add(1, Ordering::Relaxed);
+            #[cfg(feature = "tracing")]
+            tracing::info!(
+                backlog = self.available(),
+                speed_ups = self.speed_ups(),
+                "ring buffer reader speeding up"
+            );
+        } else if current > 1.0 && rate == 1.0 {
+            #[cfg(feature = "tracing")]
+            tracing::info!(
+                backlog = self.available(),
+                "ring buffer reader back to real time"
+            );
+        }
+
+        self.catch_up_rate.store(rate.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Current pacing speed relative to real time; above 1.0 while a
+    /// `CatchUp::SpeedUp` strategy is working off a backlog.
+    pub fn catch_up_rate(&self) -> f64 {
+        f64::from_bits(self.catch_up_rate.load(Ordering::Relaxed))
+    }
+
+    pub fn is_speeding_up(&self) -> bool {
+        self.catch_up_rate() > 1.0
+    }
+
+    /// Number of times a `CatchUp::SpeedUp` strategy started working off a
+    /// backlog.
+    pub fn speed_ups(&self) -> u64 {
+        self.speed_ups.load(Ordering::Relaxed)
+    }
+
+    /// Claims the next unread block regardless of pacing, skipping to the
+    /// oldest resident block if the writer has lapped the cursor.
+    pub fn claim_next(&self) -> Option<u64> {
+        self.thread_check.note_reader();
+        self.evict_aged();
+
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        let oldest = total_writes.saturating_sub(self.ring_buffer_size as u64);
+        let seq = self.total_reads.load(Ordering::Relaxed).max(oldest);
+        if seq >= total_writes {
+            return None;
+        }
+
+        self.debug_assert_claim(seq, total_writes);
+        self.advance_reads(seq, 1);
+        self.record_e2e_latency(seq);
+        self.check_health();
+        Some(seq)
+    }
+
+    // The only place total_reads moves: the read head becomes `from + n`,
+    // clamped so it never passes total_writes. Returns the new head.
+    fn advance_reads(&self, from: u64, n: u64) -> u64 {
+        let total_reads = from
+            .saturating_add(n)
+            .min(self.total_writes.load(Ordering::Acquire));
+        self.total_reads.store(total_reads, Ordering::Release);
+        self.notify_capacity();
+        total_reads
+    }
+
+    /// Offline rendering: writes each block `produce` returns and drains it
+    /// straight away with unpaced reads, until `produce` returns `None`.
+    /// Returns everything rendered back to back. Stops early if a block
+    /// can't be written, e.g. because it is the wrong size.
+    pub fn render_all(&self, mut produce: impl FnMut() -> Option<Vec<f32>>) -> Vec<f32> {
+        // writing and reading on one thread is the point here
+        self.thread_check.allow_same_thread();
+        let mut rendered = Vec::new();
+        let mut block = vec![0.0; self.buffer_size];
+        while let Some(data) = produce() {
+            if self.write(data).is_err() {
+                break;
+            }
+            while let Ok(true) = self.read_next_into(&mut block) {
+                rendered.extend_from_slice(&block);
+            }
+        }
+
+        rendered
+    }
+
+    /// Skips up to `n` unread blocks from the read head without copying
+    /// them, e.g. to drop the click at the start of a recording. Returns how
+    /// many were skipped; fewer than `n` when fewer are unread.
+    pub fn discard_blocks(&self, n: usize) -> usize {
+        let start = self.unread_seqs().start;
+        (self.skip_reads(start, n as u64) - start) as usize
+    }
+
+    /// Unpaced read of the next unread block into `out`. Returns `false`
+    /// when there is nothing new to read.
+    pub fn read_next_into(&self, out: &mut [f32]) -> Result<bool, RingBufferError> {
+        if out.len() != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        Ok(self.claim_next_into(out))
+    }
+
+    // Claims the next unread block and copies it into the block-sized
+    // `out`. A block the writer laps between the claim and the copy is lost
+    // either way, so the one after it is taken instead. False once nothing
+    // is unread.
+    fn claim_next_into(&self, out: &mut [f32]) -> bool {
+        while let Some(seq) = self.claim_next() {
+            if self.copy_seq_into(seq, out).is_ok() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Claims every unread block and returns their samples back to back,
+    /// with trailing samples quieter than `silence_threshold` cut off, for
+    /// exporting a recording without the silence after it.
+    pub fn drain_trimmed(&self, silence_threshold: f32) -> Vec<f32> {
+        let mut samples = self.drain();
+        let end = samples
+            .iter()
+            .rposition(|s| s.abs() >= silence_threshold)
+            .map_or(0, |last| last + 1);
+        samples.truncate(end);
+        samples
+    }
+
+    /// Drains the ring like [`drain_trimmed`](Self::drain_trimmed), without
+    /// trimming, and checks every sample against `reference` to within
+    /// `epsilon`, for golden-file tests. On failure returns the first bad
+    /// index with the drained and reference values; a side that ran out of
+    /// samples first reports `NaN` there.
+    pub fn compare_to(&self, reference: &[f32], epsilon: f32) -> Result<(), (usize, f32, f32)> {
+        let samples = self.drain();
+        for index in 0..samples.len().max(reference.len()) {
+            let actual = samples.get(index).copied().unwrap_or(f32::NAN);
+            let expected = reference.get(index).copied().unwrap_or(f32::NAN);
+            // false when either side is NaN, so a missing sample fails
+            let close = (actual - expected).abs() <= epsilon;
+            if !close {
+                return Err((index, actual, expected));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Claims up to `out.len() / buffer_size` unread blocks regardless of
+    /// pacing and copies them back to back into `out`, for consumers that
+    /// need a fixed-size chunk spanning several blocks. When fewer blocks
+    /// remain, as at the end of a drain, the rest of `out` is zero-padded.
+    /// Returns the number of real samples copied. Errors with
+    /// `DataSizeMismatch` unless `out` is a whole number of blocks.
+    pub fn read_coalesced_padded(&self, out: &mut [f32]) -> Result<usize, RingBufferError> {
+        if !out.len().is_multiple_of(self.buffer_size) {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        let mut filled = 0;
+        while filled < out.len()
+            && self.claim_next_into(&mut out[filled..filled + self.buffer_size])
+        {
+            filled += self.buffer_size;
+        }
+        out[filled..].fill(0.0);
+
+        Ok(filled)
+    }
+
+    // Claims every unread block and returns their samples back to back.
+    fn drain(&self) -> Vec<f32> {
+        let mut samples = Vec::with_capacity(self.available() as usize * self.buffer_size);
+        let mut block = vec![0.0; self.buffer_size];
+        while self.claim_next_into(&mut block) {
+            samples.extend_from_slice(&block);
+        }
+
+        samples
+    }
+
+    /// Samples per block, counting every channel: a stereo block of 512
+    /// holds 256 frames.
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    pub fn ring_buffer_size(&self) -> usize {
+        self.ring_buffer_size
+    }
+
+    /// Frames per second, i.e. per channel.
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate as usize
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Frames per block, `buffer_size / channels`. A frame is one sample
+    /// for every channel; the block period is this over `sample_rate`.
+    pub fn frames_per_block(&self) -> usize {
+        self.buffer_size / self.channels
+    }
+
+    /// Samples written since construction, counting every channel.
+    pub fn samples_written(&self) -> u64 {
+        self.total_writes() * self.buffer_size as u64
+    }
+
+    /// Frames written since construction.
+    pub fn frames_written(&self) -> u64 {
+        self.total_writes() * self.frames_per_block() as u64
+    }
+
+    /// Samples in the blocks claimed by the reader so far.
+    pub fn samples_read(&self) -> u64 {
+        self.total_reads() * self.buffer_size as u64
+    }
+
+    /// Frames in the blocks claimed by the reader so far.
+    pub fn frames_read(&self) -> u64 {
+        self.total_reads() * self.frames_per_block() as u64
+    }
+
+    /// Samples in the unread blocks, counting every channel.
+    pub fn samples_available(&self) -> u64 {
+        self.available() * self.buffer_size as u64
+    }
+
+    /// Frames in the unread blocks; over `sample_rate` this is the time
+    /// they take to play.
+    pub fn frames_available(&self) -> u64 {
+        self.available() * self.frames_per_block() as u64
+    }
+
+    /// Worst-case delay a sample can see: the time span of a full ring,
+    /// `ring_buffer_size * frames_per_block / sample_rate`.
+    pub fn max_latency(&self) -> Duration {
+        let frames = self.ring_buffer_size as f64 * self.frames_per_block() as f64;
+        Duration::from_secs_f64(frames / self.sample_rate as f64)
+    }
+
+    /// Best-case delay: one block period, `frames_per_block / sample_rate`.
+    pub fn min_latency(&self) -> Duration {
+        Duration::from_secs_f64(self.frames_per_block() as f64 / self.sample_rate as f64)
+    }
+
+    // Skips, in one step, every unread block older than max_age.
+    fn evict_aged(&self) {
+        let Some(max_age) = self.max_age else {
+            return;
+        };
+
+        let now = self.now_nanos();
+        let max_age = Self::to_nanos(&max_age);
+        let seqs = self.unread_seqs();
+        let mut seq = seqs.start;
+        while seq < seqs.end {
+            let written_at = self.written_at[self.slot_index(seq)].load(Ordering::Relaxed);
+            if now.saturating_sub(written_at) <= max_age {
+                break;
+            }
+            seq += 1;
+        }
+
+        if seq > seqs.start {
+            let skipped = self.skip_reads(seqs.start, seq - seqs.start) - seqs.start;
+            self.aged_out.fetch_add(skipped, Ordering::Relaxed);
+        }
+    }
+
+    // advance_reads for blocks that are dropped rather than played. The
+    // last of them becomes the current block, so remember that it was
+    // skipped and must not be served by reads that don't advance.
+    fn skip_reads(&self, from: u64, n: u64) -> u64 {
+        let total_reads = self.advance_reads(from, n);
+        if total_reads > from {
+            self.skipped_through.store(total_reads, Ordering::Relaxed);
+        }
+        total_reads
+    }
+
+    // Whether `seq` was passed over by skip_reads instead of being claimed.
+    fn was_skipped(&self, seq: u64) -> bool {
+        seq < self.skipped_through.load(Ordering::Relaxed)
+    }
+
+    /// Samples per second the producer has written, measured across the
+    /// last few writes (gaps included) whatever the ring size. Zero until
+    /// two blocks have been written at different times. Compare with
+    /// `sample_rate * channels` to throttle a producer to real time.
+    pub fn measured_write_rate(&self) -> f32 {
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        // skip the oldest entry, which a concurrent write may be replacing
+        let first = total_writes.saturating_sub(WRITE_RATE_WINDOW - 1);
+        let Some(last) = total_writes.checked_sub(1).filter(|&last| last > first) else {
+            return 0.0;
+        };
+
+        let write_time =
+            |seq: u64| self.write_times[(seq % WRITE_RATE_WINDOW) as usize].load(Ordering::Relaxed);
+        let elapsed = write_time(last).saturating_sub(write_time(first));
+        if elapsed == 0 {
+            return 0.0;
+        }
+
+        let samples = (last - first) as f64 * self.buffer_size as f64;
+        (samples / Duration::from_nanos(elapsed).as_secs_f64()) as f32
+    }
+
+    /// Number of paced reads that found no new block when one was due.
+    /// Gaps from `write_gap` are blocks, so they never count.
+    pub fn underruns(&self) -> u64 {
+        self.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Latency of the unread blocks, averaged over paced reads with an EMA
+    /// (alpha 0.1): each read moves the estimate a tenth of the way towards
+    /// the lag it saw, which gives a steady readout for a UI. Zero before
+    /// the first paced read.
+    pub fn smoothed_latency(&self) -> Duration {
+        let blocks = f64::from_bits(self.latency_ema.load(Ordering::Relaxed));
+        if blocks.is_nan() {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(blocks * self.frames_per_block() as f64 / self.sample_rate as f64)
+    }
+
+    /// Time from a block being written to it being claimed by a read,
+    /// averaged over recent claims with an EMA (alpha 0.1). Unlike
+    /// [`smoothed_latency`](Self::smoothed_latency), which models latency
+    /// from buffer depth, this includes real scheduling delays. Zero before
+    /// the first claim.
+    pub fn measured_e2e_latency(&self) -> Duration {
+        let nanos = f64::from_bits(self.e2e_latency.load(Ordering::Relaxed));
+        if nanos.is_nan() {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos(nanos as u64)
+        }
+    }
+
+    // Only the reader calls this, so a plain load and store is enough.
+    fn record_lag(&self) {
+        let lag = self.available() as f64;
+        let ema = f64::from_bits(self.latency_ema.load(Ordering::Relaxed));
+        let ema = if ema.is_nan() {
+            lag
+        } else {
+            ema + (lag - ema) * 0.1
+        };
+        self.latency_ema.store(ema.to_bits(), Ordering::Relaxed);
+    }
+
+    fn record_e2e_latency(&self, seq: u64) {
+        let written_at = self.written_at[self.slot_index(seq)].load(Ordering::Relaxed);
+        let latency = self.now_nanos().saturating_sub(written_at) as f64;
+        let ema = f64::from_bits(self.e2e_latency.load(Ordering::Relaxed));
+        let ema = if ema.is_nan() {
+            latency
+        } else {
+            ema + (latency - ema) * 0.1
+        };
+        self.e2e_latency.store(ema.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Most unread blocks seen right after a write since construction. Can
+    /// exceed `ring_buffer_size` when the reader fell a whole ring behind.
+    pub fn peak_lag(&self) -> u64 {
+        self.peak_lag.load(Ordering::Relaxed)
+    }
+
+    /// Suggested `ring_buffer_size` for this workload: twice the peak lag
+    /// seen so far, for headroom, and never smaller than the current size.
+    pub fn recommended_ring_size(&self) -> usize {
+        (self.peak_lag() as usize)
+            .saturating_mul(2)
+            .max(self.ring_buffer_size)
+    }
+
+    /// Number of times a paced read snapped forward to the newest block.
+    pub fn catch_ups(&self) -> u64 {
+        self.catch_ups.load(Ordering::Relaxed)
+    }
+
+    /// Parks the calling thread until the next catch-up after this call,
+    /// `timeout` passes or the ring is closed. Returns whether a catch-up
+    /// happened. Only one thread should wait at a time.
+    pub fn wait_for_catchup(&self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let start = self.catch_ups.load(Ordering::Acquire);
+        self.catch_up_waiter
+            .wait(deadline, || {
+                if self.catch_ups.load(Ordering::Acquire) > start {
+                    Some(true)
+                } else if self.is_closed() {
+                    Some(false)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(false)
+    }
+
+    fn notify_catch_up(&self) {
+        self.catch_up_waiter.notify();
+    }
+
+    /// Number of reads whose output came from the underrun fill callback.
+    pub fn synthetic_fills(&self) -> u64 {
+        self.synthetic_fills.load(Ordering::Relaxed)
+    }
+
+    /// Registers a generator used instead of the normal output when a paced
+    /// read underruns or has nothing to serve yet. It runs on the reader
+    /// thread inside `read_into`, so it must not block, allocate or take
+    /// locks. Without one, underruns repeat the current block.
+    pub fn set_underrun_fill(&self, f: impl FnMut(&mut [f32]) + Send + 'static) {
+        *self.underrun_fill.lock().unwrap() = Some(Box::new(f));
+    }
+
+    /// Registers a callback that receives each unread block just before a
+    /// write overwrites it, e.g. to spill it to disk instead of losing it.
+    /// It runs on the writer thread inside `write`.
+    pub fn set_overflow_handler(&self, handler: OverflowHandler) {
+        *self.overflow_handler.lock().unwrap() = Some(handler);
+    }
+
+    /// Number of blocks skipped so far for exceeding `max_age`.
+    pub fn aged_out(&self) -> u64 {
+        self.aged_out.load(Ordering::Relaxed)
+    }
+
+    /// Number of written blocks the reader has not claimed yet.
+    pub fn available(&self) -> u64 {
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        total_writes.saturating_sub(self.total_reads.load(Ordering::Acquire))
+    }
+
+    pub fn total_writes(&self) -> u64 {
+        self.total_writes.load(Ordering::Relaxed)
+    }
+
+    /// Blocks the read head has moved past, played or skipped. Never
+    /// exceeds [`total_writes`](Self::total_writes): every read path stops
+    /// at the write head, so there is no separate read-ahead limit to set.
+    pub fn total_reads(&self) -> u64 {
+        self.total_reads.load(Ordering::Relaxed)
+    }
+
+    /// The sequence of the most recently claimed block, if any.
+    pub fn current_seq(&self) -> Option<u64> {
+        self.total_reads.load(Ordering::Acquire).checked_sub(1)
+    }
+
+    /// Copies the block with sequence `seq` into `out`. Fails with
+    /// `SequenceNotResident` if it has not been written yet or its slot has
+    /// already been reused. With clear-on-read enabled, a block that was
+    /// already delivered copies as silence.
+    pub fn copy_seq_into(&self, seq: u64, out: &mut [f32]) -> Result<(), RingBufferError> {
+        if out.len() != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        self.copy_seq_prefix_into(seq, out)
+    }
+
+    // copy_seq_into for an `out` no longer than a block, filling it with
+    // the start of the block.
+    fn copy_seq_prefix_into(&self, seq: u64, out: &mut [f32]) -> Result<(), RingBufferError> {
+        if out.len() > self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        if !self.is_resident(seq) {
+            return Err(RingBufferError::SequenceNotResident);
+        }
+
+        if self.clear_on_read {
+            // delivered stores seq + 1 so that 0 means nothing delivered yet
+            let delivered = &self.delivered[self.slot_index(seq)];
+            if delivered.swap(seq + 1, Ordering::Relaxed) == seq + 1 {
+                out.fill(0.0);
+                return Ok(());
+            }
+        }
+
+        self.copy_slot_into(seq, out);
+        if !self.not_overwritten(seq) {
+            return Err(RingBufferError::SequenceNotResident);
+        }
+
+        Ok(())
+    }
+
+    // Copies without marking the block delivered, but still honours
+    // clear-on-read for blocks a reader already has.
+    fn peek_seq_into(&self, seq: u64, out: &mut [f32]) {
+        let delivered = self.delivered[self.slot_index(seq)].load(Ordering::Relaxed);
+        if self.clear_on_read && delivered == seq + 1 {
+            out.fill(0.0);
+        } else {
+            self.copy_slot_into(seq, out);
+        }
+    }
+
+    fn copy_slot_into(&self, seq: u64, out: &mut [f32]) {
+        if self.is_gap(seq) {
+            out.fill(0.0);
+            return;
+        }
+
+        // out may be a prefix of the block, see read_prefix_into
+        let data = self.pin_slot(self.slot(seq));
+        out.copy_from_slice(&data[..out.len().min(data.len())]);
+    }
+
+    // Written and not yet overwritten.
+    fn is_resident(&self, seq: u64) -> bool {
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        seq < total_writes && total_writes <= seq + self.ring_buffer_size as u64
+    }
+
+    // Whether the writer hasn't started on the write that replaces `seq`.
+    // Checked once done with the slot, since the writer may have swapped in
+    // a later block or flipped the gap flag meanwhile.
+    fn not_overwritten(&self, seq: u64) -> bool {
+        fence(Ordering::Acquire);
+        self.writes_begun.load(Ordering::Relaxed) <= seq + self.ring_buffer_size as u64
+    }
+
+    fn is_gap(&self, seq: u64) -> bool {
+        self.gaps[self.slot_index(seq)].load(Ordering::Relaxed)
+    }
+
+    // Sequences of the unread blocks that are still resident. Slots that
+    // were never written are below total_writes - ring, so never included.
+    fn unread_seqs(&self) -> std::ops::Range<u64> {
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        let oldest = total_writes.saturating_sub(self.ring_buffer_size as u64);
+        self.total_reads.load(Ordering::Acquire).max(oldest)..total_writes
+    }
+
+    /// Copies the most recently written block into `out` without moving the
+    /// read cursor, for visualisation. Silence before the first write.
+    pub fn read_latest(&self, out: &mut [f32]) -> Result<(), RingBufferError> {
+        if out.len() != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        match self.total_writes.load(Ordering::Acquire).checked_sub(1) {
+            Some(seq) => self.peek_seq_into(seq, out),
+            None => out.fill(0.0),
+        }
+
+        Ok(())
+    }
+
+    /// Fills `out` with a linear blend from the block before the current
+    /// one to the current one, by how far through the block period the
+    /// pacing clock is. Repainting faster than blocks arrive then shows
+    /// continuous motion. Blocks that aren't resident count as silence.
+    pub fn peek_interpolated(&self, out: &mut [f32]) -> Result<(), RingBufferError> {
+        if out.len() != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        let Some(current) = self.current_seq() else {
+            out.fill(0.0);
+            return Ok(());
+        };
+
+        let mut previous = vec![0.0; self.buffer_size];
+        if let Some(seq) = current.checked_sub(1).filter(|&seq| self.is_resident(seq)) {
+            self.peek_seq_into(seq, &mut previous);
+        }
+        if self.is_resident(current) {
+            self.peek_seq_into(current, out);
+        } else {
+            out.fill(0.0);
+        }
+
+        let t = (self.elapsed_since_advance().as_secs_f32() / self.block_period()).min(1.0);
+        for (sample, previous) in out.iter_mut().zip(&previous) {
+            *sample = previous + (*sample - previous) * t;
+        }
+
+        Ok(())
+    }
+
+    /// Fills `out` with the most recent `out.len()` written samples, oldest
+    /// first, without moving the read cursor. Any part of the window older
+    /// than the first write or the oldest resident block is silent.
+    pub fn analysis_window(&self, out: &mut [f32]) {
+        out.fill(0.0);
+        if self.buffer_size == 0 {
+            return;
+        }
+
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        let oldest = total_writes.saturating_sub(self.ring_buffer_size as u64);
+        let mut block = vec![0.0; self.buffer_size];
+        let mut end = out.len();
+        for seq in (oldest..total_writes).rev() {
+            if end == 0 {
+                break;
+            }
+            self.peek_seq_into(seq, &mut block);
+            let take = end.min(self.buffer_size);
+            out[end - take..end].copy_from_slice(&block[self.buffer_size - take..]);
+            end -= take;
+        }
+    }
+
+    /// Copies all unread audio without consuming it, along with the sample
+    /// rate and channel count needed for a WAV header. Only blocks actually
+    /// written are included, so a ring that hasn't wrapped yet yields fewer
+    /// than `ring_buffer_size` blocks.
+    pub fn to_interleaved(&self) -> (Vec<f32>, u32, u16) {
+        let seqs = self.unread_seqs();
+        let mut data = vec![0.0; (seqs.end - seqs.start) as usize * self.buffer_size];
+        for (seq, block) in seqs.zip(data.chunks_exact_mut(self.buffer_size)) {
+            self.copy_slot_into(seq, block);
+        }
+
+        (data, self.sample_rate as u32, self.channels as u16)
+    }
+
+    // An empty buffer with the same geometry, paced by the same clock (so
+    // it works wherever self does, e.g. with PerformanceClock on wasm).
+    fn snapshot_geometry(&self) -> RingBuffer {
+        Self::construct(
+            self.buffer_size,
+            self.ring_buffer_size,
+            self.sample_rate(),
+            0.0,
+            Arc::clone(&self.clock),
+        )
+        .with_channels(self.channels)
+    }
+
+    /// Copies the unread blocks into a new, independent buffer with the
+    /// same geometry, paced by the same clock with pacing restarted. Its
+    /// counters start over, so all of the copied audio is unread there; the
+    /// live buffer is untouched.
+    pub fn snapshot_buffer(&self) -> RingBuffer {
+        let snapshot = self.snapshot_geometry();
+
+        let mut block = vec![0.0; self.buffer_size];
+        for seq in self.unread_seqs() {
+            // the snapshot is open and sized like self, so writes can't fail
+            if self.is_gap(seq) {
+                let _ = snapshot.write_gap(1);
+            } else {
+                self.copy_slot_into(seq, &mut block);
+                let _ = snapshot.write_slice(&block);
+            }
+        }
+
+        snapshot
+    }
+
+    /// Startup sanity check: on a scratch copy of this buffer's slots and
+    /// counters, checks their consistency, then writes a ramp across every
+    /// slot, reads it back unpaced and compares. Live state is untouched.
+    /// Fails with `SelfTestFailed` if the copy is inconsistent or on any
+    /// mismatch.
+    pub fn self_test(&self) -> Result<(), RingBufferError> {
+        let scratch = self.scratch_copy();
+        scratch.thread_check.allow_same_thread();
+        if scratch.validate().is_err() {
+            return Err(RingBufferError::SelfTestFailed);
+        }
+        // so the ramp doesn't overrun what was unread
+        scratch.discard_blocks(self.ring_buffer_size);
+        let ramp = |seq: usize, i: usize| ((seq * self.buffer_size + i) % (1 << 24)) as f32;
+
+        for seq in 0..self.ring_buffer_size {
+            let block = (0..self.buffer_size).map(|i| ramp(seq, i)).collect();
+            scratch.write(block)?;
+        }
+
+        let mut block = vec![0.0; self.buffer_size];
+        for seq in 0..self.ring_buffer_size {
+            if !scratch.read_next_into(&mut block)?
+                || block.iter().enumerate().any(|(i, &s)| s != ramp(seq, i))
+            {
+                return Err(RingBufferError::SelfTestFailed);
+            }
+        }
+
+        if scratch.read_next_into(&mut block)? || scratch.validate().is_err() {
+            return Err(RingBufferError::SelfTestFailed);
+        }
+
+        Ok(())
+    }
+
+    // A buffer with the same geometry holding copies of this one's slots,
+    // whatever their length, gap flags and counters, for self_test.
+    fn scratch_copy(&self) -> RingBuffer {
+        let scratch = self.snapshot_geometry();
+        for (index, slot) in self.buffers.iter().enumerate() {
+            let data: Block = Box::new(self.pin_slot(slot).to_vec());
+            let old_data = scratch.buffers[index].swap(Box::into_raw(data), Ordering::Relaxed);
+            unsafe {
+                _ = Box::from_raw(old_data);
+            }
+            let gap = self.gaps[index].load(Ordering::Relaxed);
+            scratch.gaps[index].store(gap, Ordering::Relaxed);
+        }
+
+        // reads first: writes only grow, so a stale writes can't look behind
+        let total_reads = self.total_reads.load(Ordering::Acquire);
+        let total_writes = self.total_writes.load(Ordering::Acquire);
+        scratch.total_reads.store(total_reads, Ordering::Relaxed);
+        scratch.total_writes.store(total_writes, Ordering::Relaxed);
+        scratch.writes_begun.store(total_writes, Ordering::Relaxed);
+        scratch
+    }
+
+    /// Consumes the buffer and hands back every slot's storage, oldest
+    /// first, ending with the most recent write. Slots not yet written and
+    /// gap slots come back as silence, like they would read.
+    pub fn into_blocks(self) -> Vec<Vec<f32>> {
+        let total_writes = self.total_writes.load(Ordering::Relaxed);
+        let ring = self.ring_buffer_size as u64;
+
+        // leaves null behind, which Drop skips
+        (total_writes..total_writes + ring)
+            .map(|i| {
+                let slot = &self.buffers[self.slot_index(i)];
+                let mut data =
+                    *unsafe { Box::from_raw(slot.swap(std::ptr::null_mut(), Ordering::Relaxed)) };
+                // the slot holds write i - ring, if there was one
+                if i < ring || self.is_gap(i - ring) {
+                    data.fill(0.0);
+                }
+                data
+            })
+            .collect()
+    }
+
+    /// Paced read into a caller-provided buffer. Fills `out` with silence
+    /// until the first block has been claimed.
+    pub fn read_into(&self, out: &mut [f32]) -> Result<ReadOutcome, RingBufferError> {
+        if out.len() != self.buffer_size {
+            return Err(RingBufferError::DataSizeMismatch);
+        }
+
+        self.read_into_unchecked(out)
+    }
+
+    /// [`read_into`](Self::read_into) without the up-front length check.
+    /// Debug builds assert `out.len() == buffer_size`; in release a wrong
+    /// length is handed to the underrun fill callback as is, a short `out`
+    /// gets the start of the block, and a long one is reported as
+    /// `DataSizeMismatch` after the cursor has already moved.
+    pub fn read_into_unchecked(&self, out: &mut [f32]) -> Result<ReadOutcome, RingBufferError> {
+        debug_assert_eq!(out.len(), self.buffer_size);
+
+        self.read_claimed_into(out)
+    }
+
+    /// Paced read that copies only the first `min(out.len(), buffer_size)`
+    /// samples of the block, for cheap previews. The cursor advances just
+    /// as with [`read_into`](Self::read_into). Returns the number of
+    /// samples copied; the underrun fill callback also sees only that many.
+    pub fn read_prefix_into(&self, out: &mut [f32]) -> Result<usize, RingBufferError> {
+        let len = out.len().min(self.buffer_size);
+        self.read_claimed_into(&mut out[..len])?;
+
+        Ok(len)
+    }
+
+    fn read_claimed_into(&self, out: &mut [f32]) -> Result<ReadOutcome, RingBufferError> {
+        let outcome = self.begin_read();
+        self.render_into(outcome, out)
+    }
+
+    /// Paced read that hands the block to `f` along with what was
+    /// delivered, instead of copying it into caller memory. Whether `f`
+    /// sees a copy or the slot itself is up to the
+    /// [`ReadStrategy`](Self::with_read_strategy); the data is the same
+    /// either way. Blocks that need processing on the way out (gain, soft
+    /// clipping, clear-on-read, underrun fill, gaps) are always copied. A
+    /// borrowed slot can't be recycled until `f` returns, so keep it short.
+    pub fn read_with<R>(
+        &self,
+        f: impl FnOnce(&[f32], ReadOutcome) -> R,
+    ) -> Result<R, RingBufferError> {
+        let mut outcome = self.begin_read();
+        if let Some(seq) = outcome.seq.filter(|_| self.can_lend(&outcome)) {
+            match self.pin(seq) {
+                Some(pinned) => {
+                    let result = f(&pinned, outcome);
+                    drop(pinned);
+                    self.debug_assert_invariants();
+                    return Ok(result);
+                }
+                None => {
+                    outcome.seq = None;
+                    outcome.overwritten = true;
+                }
+            }
+        }
+
+        // try_lock: a callback that reads again gets its own buffer
+        let mut owned = Vec::new();
+        let mut scratch = self.read_scratch.try_lock();
+        let out = match scratch.as_deref_mut() {
+            Ok(scratch) => scratch,
+            Err(_) => {
+                owned.resize(self.buffer_size, 0.0);
+                &mut owned
+            }
+        };
+        let outcome = self.render_into(outcome, out)?;
+
+        Ok(f(out, outcome))
+    }
+
+    // Whether read_with can hand out the slot as is.
+    fn can_lend(&self, outcome: &ReadOutcome) -> bool {
+        self.borrows()
+            && !outcome.underrun
+            && !outcome.intentional
+            && !self.clear_on_read
+            && self.agc.is_none()
+            && self.soft_clip.is_none()
+    }
+
+    // Pins the block holding `seq` so the writer won't recycle it until the
+    // returned guard is dropped. None once the writer has lapped it.
+    fn pin(&self, seq: u64) -> Option<Pinned<'_>> {
+        let pinned = self.pin_slot(self.slot(seq));
+        (self.is_resident(seq) && self.not_overwritten(seq)).then_some(pinned)
+    }
+
+    // Pins whatever block `slot` holds right now.
+    fn pin_slot(&self, slot: &AtomicPtr<Vec<f32>>) -> Pinned<'_> {
+        let hazard = self.claim_hazard();
+        loop {
+            let data_ptr = slot.load(Ordering::Acquire);
+            hazard.store(data_ptr, Ordering::Release);
+            fence(Ordering::SeqCst);
+            if slot.load(Ordering::Acquire) == data_ptr {
+                return Pinned {
+                    hazard,
+                    data: unsafe { &*data_ptr },
+                };
+            }
+        }
+    }
+
+    // A hazard no other reader is using, waiting for one if all are.
+    fn claim_hazard(&self) -> &AtomicPtr<Vec<f32>> {
+        loop {
+            for hazard in &self.hazards {
+                let claimed = hazard.compare_exchange(
+                    std::ptr::null_mut(),
+                    HAZARD_RESERVED,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                );
+                if claimed.is_ok() {
+                    return hazard;
+                }
+            }
+            thread::yield_now();
+        }
+    }
+
+    // The reading half of read_claimed_into: moves the cursor, updates the
+    // read statistics and decides which block to serve.
+    fn begin_read(&self) -> ReadOutcome {
+        let (decision, claimed) = self.advance_with_decision();
+        self.record_lag();
+        self.last_read_fresh
+            .store(claimed.is_some(), Ordering::Relaxed);
+        self.last_read_underrun
+            .store(decision == AdvanceDecision::Starved, Ordering::Relaxed);
+        self.check_health();
+
+        // a read that doesn't advance keeps serving the current block,
+        // unless that block was skipped rather than played
+        let seq = claimed.or_else(|| self.current_seq().filter(|&seq| !self.was_skipped(seq)));
+        // or the writer may have lapped it while it was being held
+        let overwritten = seq.is_some_and(|seq| !self.is_resident(seq));
+        let seq = seq.filter(|_| !overwritten);
+        ReadOutcome {
+            seq,
+            fresh: claimed.is_some(),
+            intentional: seq.is_some_and(|seq| self.is_gap(seq)),
+            underrun: decision == AdvanceDecision::Starved,
+            synthetic: false,
+            overwritten,
+        }
+    }
+
+    // The writing half of read_claimed_into: fills `out` with the block
+    // begin_read chose, the underrun fill or silence.
+    fn render_into(
+        &self,
+        mut outcome: ReadOutcome,
+        out: &mut [f32],
+    ) -> Result<ReadOutcome, RingBufferError> {
+        let seq = outcome.seq;
+
+        if outcome.underrun || seq.is_none() {
+            // try_lock so a concurrent set_underrun_fill can't block the reader
+            if let Ok(mut fill) = self.underrun_fill.try_lock() {
+                if let Some(fill) = fill.as_mut() {
+                    fill(out);
+                    self.apply_agc(out);
+                    self.soft_clip(out);
+                    self.synthetic_fills.fetch_add(1, Ordering::Relaxed);
+                    outcome.synthetic = true;
+                    return Ok(outcome);
+                }
+            }
+        }
+
+        match seq.map(|seq| self.copy_seq_prefix_into(seq, out)) {
+            Some(Ok(())) => {}
+            // lapped since begin_read checked it
+            Some(Err(RingBufferError::SequenceNotResident)) => {
+                out.fill(0.0);
+                outcome.seq = None;
+                outcome.overwritten = true;
+            }
+            Some(Err(err)) => return Err(err),
+            None => out.fill(0.0),
+        }
+        self.apply_agc(out);
+        self.soft_clip(out);
+        self.debug_assert_invariants();
+
+        Ok(outcome)
+    }
+
+    /// Paced read of one interleaved stereo block, split into `(left, right)`.
+    pub fn read_planar(&self) -> Result<(Vec<f32>, Vec<f32>), RingBufferError> {
+        if self.channels != 2 {
+            return Err(RingBufferError::ChannelMismatch);
+        }
+
+        let block = self.read();
+        let left = block.iter().step_by(2).copied().collect();
+        let right = block.iter().skip(1).step_by(2).copied().collect();
+
+        Ok((left, right))
+    }
+
+    /// Whether the most recent paced read advanced to a new block, as opposed
+    /// to repeating the current one.
+    pub fn last_read_was_fresh(&self) -> bool {
+        self.last_read_fresh.load(Ordering::Relaxed)
+    }
+
+    pub fn read(&self) -> Vec<f32> {
+        self.read_seq().0
+    }
+
+    /// Like [`read`](Self::read), also returning the sequence number of the
+    /// block served, so a consumer can spot skipped blocks. The sequence is
+    /// `None` while silence is served before the first block.
+    pub fn read_seq(&self) -> (Vec<f32>, Option<u64>) {
+        let mut data = vec![0.0; self.buffer_size];
+        match self.read_into(&mut data) {
+            Ok(outcome) => (data, outcome.seq),
+            Err(_) => {
+                data.fill(0.0);
+                (data, None)
+            }
+        }
+    }
+}
+
+/// Mono, [`DEFAULT_BUFFER_SIZE`] samples per block, [`DEFAULT_RING_BUFFER_SIZE`]
+/// blocks at [`DEFAULT_SAMPLE_RATE`] Hz, paced by the system clock: about
+/// 85 ms of audio, for prototyping.
+impl Default for RingBuffer {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_BUFFER_SIZE,
+            DEFAULT_RING_BUFFER_SIZE,
+            DEFAULT_SAMPLE_RATE,
+        )
+    }
+}
+
+impl Drop for RingBuffer {
+    fn drop(&mut self) {
+        for buffer in self.buffers.iter() {
+            // null once into_blocks has taken the slot
+            let data_ptr = buffer.load(Ordering::Relaxed);
+            if !data_ptr.is_null() {
+                unsafe {
+                    _ = Box::from_raw(data_ptr);
+                }
+            }
+        }
+
+        let data_ptr = self.spare.load(Ordering::Relaxed);
+        if !data_ptr.is_null() {
+            unsafe {
+                _ = Box::from_raw(data_ptr);
+            }
+        }
+    }
+}
+
+// A block a reader has pinned in one of the hazards. Dropping it frees the
+// hazard and lets the writer recycle the block again.
+struct Pinned<'a> {
+    hazard: &'a AtomicPtr<Vec<f32>>,
+    data: &'a Vec<f32>,
+}
+
+impl Deref for Pinned<'_> {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        self.data
+    }
+}
+
+impl Drop for Pinned<'_> {
+    fn drop(&mut self) {
+        self.hazard.store(std::ptr::null_mut(), Ordering::Release);
+    }
+}
+
+/// A block being filled in place, from [`RingBuffer::write_guard`].
+/// Dropping it without committing discards the block.
+pub struct WriteGuard<'a> {
+    ring: &'a RingBuffer,
+    data: Option<Block>,
+}
+
+impl WriteGuard<'_> {
+    /// Publishes the block as the next write.
+    pub fn commit(mut self) -> Result<(), RingBufferError> {
+        let Some(data) = self.data.take() else {
+            return Err(RingBufferError::DataSizeMismatch);
+        };
+        if self.ring.is_closed() {
+            self.ring.recycle(data);
+            return Err(RingBufferError::Closed);
+        }
+
+        self.ring.publish(data);
+
+        Ok(())
+    }
+}
+
+impl Deref for WriteGuard<'_> {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        self.data.as_deref().map_or(&[], |data| data.as_slice())
+    }
+}
+
+impl DerefMut for WriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut [f32] {
+        self.data
+            .as_deref_mut()
+            .map_or(&mut [], |data| data.as_mut_slice())
+    }
+}
+
+impl Drop for WriteGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(data) = self.data.take() {
+            self.ring.recycle(data);
+        }
     }
 }
@@ -0,0 +1,217 @@
+use crate::RingBuffer;
+use std::sync::atomic::Ordering;
+
+impl RingBuffer {
+    /// RMS level across every unread block currently in the ring, taken
+    /// from one snapshot. Slots not yet written since construction are
+    /// ignored rather than averaged in as zeros. Silence when nothing is
+    /// unread.
+    pub fn ring_rms(&self) -> f32 {
+        let samples = self.unread_snapshot();
+        if samples.is_empty() {
+            return 0.0;
+        }
+
+        let sum_of_squares: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+        (sum_of_squares / samples.len() as f64).sqrt() as f32
+    }
+
+    /// Peak absolute sample across every unread block currently in the ring.
+    pub fn ring_peak(&self) -> f32 {
+        self.unread_snapshot()
+            .iter()
+            .fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    /// Slices one snapshot of every unread sample into frames of `frame`
+    /// samples, each starting `hop` after the last, for batch spectral
+    /// analysis. A trailing partial frame is dropped. Panics if `hop` is 0.
+    pub fn windows(&self, frame: usize, hop: usize) -> Vec<Vec<f32>> {
+        assert!(hop > 0, "hop must be at least one sample");
+
+        let samples = self.unread_snapshot();
+        if frame == 0 || samples.len() < frame {
+            return Vec::new();
+        }
+
+        (0..=samples.len() - frame)
+            .step_by(hop)
+            .map(|start| samples[start..start + frame].to_vec())
+            .collect()
+    }
+
+    // The unread blocks as of one load of total_writes, back to back. A
+    // block the writer starts overwriting while it is copied is dropped
+    // rather than mixed in from a later write, along with the older ones
+    // before it, so what is left is still contiguous.
+    fn unread_snapshot(&self) -> Vec<f32> {
+        let seqs = self.unread_seqs();
+        let mut samples = Vec::with_capacity((seqs.end - seqs.start) as usize * self.buffer_size);
+        let mut block = vec![0.0; self.buffer_size];
+        for seq in seqs {
+            self.copy_slot_into(seq, &mut block);
+            if !self.not_overwritten(seq) {
+                samples.clear();
+                continue;
+            }
+            samples.extend_from_slice(&block);
+        }
+
+        samples
+    }
+
+    /// Correlation coefficient between left and right in the last stereo
+    /// block written, from `-1.0` (out of phase) through `0.0`
+    /// (unrelated, or silent) to `1.0` (in phase). Stays `0.0` unless the
+    /// ring is stereo and built `with_correlation_meter(true)`.
+    pub fn stereo_correlation(&self) -> f32 {
+        f32::from_bits(self.correlation.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn meter_correlation(&self, block: &[f32]) {
+        if !self.correlation_meter || self.channels != 2 {
+            return;
+        }
+
+        let (mut lr, mut ll, mut rr) = (0.0f64, 0.0f64, 0.0f64);
+        for frame in block.chunks_exact(2) {
+            let (l, r) = (frame[0] as f64, frame[1] as f64);
+            lr += l * r;
+            ll += l * l;
+            rr += r * r;
+        }
+
+        let norm = (ll * rr).sqrt();
+        let correlation = if norm > 0.0 {
+            (lr / norm).clamp(-1.0, 1.0) as f32
+        } else {
+            0.0
+        };
+        self.correlation
+            .store(correlation.to_bits(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PacingMode, RingBuffer};
+
+    #[test]
+    fn ring_levels_cover_every_unread_block() {
+        let ring = RingBuffer::new(4, 8, 1000);
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        assert_eq!((ring.ring_rms(), ring.ring_peak()), (0.0, 0.0));
+
+        // the block read first is loud, so leaving it in would show
+        ring.write(vec![1.0; 4]).unwrap();
+        ring.write(vec![0.5, -0.5, 0.5, -0.5]).unwrap();
+        ring.write(vec![-0.5; 4]).unwrap();
+        ring.write(vec![0.0, 0.0, 0.0, -0.8]).unwrap();
+        ring.read();
+
+        let squares = 4.0 * 0.25 + 4.0 * 0.25 + 0.64;
+        assert!((ring.ring_rms() - (squares / 12.0f32).sqrt()).abs() < 1e-6);
+        assert_eq!(ring.ring_peak(), 0.8);
+    }
+
+    #[test]
+    fn snapshots_stay_contiguous_while_the_writer_laps() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let ring = Arc::new(RingBuffer::new(4, 4, 1000));
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        let done = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let ring = Arc::clone(&ring);
+            let done = Arc::clone(&done);
+            std::thread::spawn(move || {
+                let mut n = 0u32;
+                while !done.load(Ordering::Relaxed) {
+                    n += 1;
+                    ring.write(vec![n as f32; 4]).unwrap();
+                }
+            })
+        };
+
+        while ring.total_writes() < 8 {
+            std::thread::yield_now();
+        }
+        // every block holds its write number, so a block from a later lap
+        // or a dropped one in the middle breaks the run
+        for _ in 0..20_000 {
+            let blocks = ring.windows(4, 4);
+            assert!(blocks
+                .iter()
+                .all(|block| block.iter().all(|&s| s == block[0])));
+            assert!(blocks.windows(2).all(|pair| pair[1][0] == pair[0][0] + 1.0));
+        }
+        done.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+    }
+
+    // Correlation after writing one stereo block of `frames` (left, right)
+    // pairs.
+    fn correlation_of(frames: impl Iterator<Item = (f32, f32)>) -> f32 {
+        let block: Vec<f32> = frames.flat_map(|(l, r)| [l, r]).collect();
+        let ring = RingBuffer::new(block.len(), 4, 1000)
+            .with_channels(2)
+            .with_correlation_meter(true);
+        ring.write(block).unwrap();
+        ring.stereo_correlation()
+    }
+
+    #[test]
+    fn correlation_tells_in_phase_from_anti_phase_and_unrelated() {
+        let sine = |i: usize| (i as f32 * 0.1).sin();
+        let cosine = |i: usize| (i as f32 * 0.1).cos();
+        // a whole number of cycles, so sine and cosine are orthogonal
+        let frames = 0..(20.0 * std::f32::consts::PI / 0.1).round() as usize;
+
+        let in_phase = correlation_of(frames.clone().map(|i| (sine(i), 0.5 * sine(i))));
+        assert!((in_phase - 1.0).abs() < 1e-5, "{in_phase}");
+
+        let anti_phase = correlation_of(frames.clone().map(|i| (sine(i), -sine(i))));
+        assert!((anti_phase + 1.0).abs() < 1e-5, "{anti_phase}");
+
+        let unrelated = correlation_of(frames.map(|i| (sine(i), cosine(i))));
+        assert!(unrelated.abs() < 0.01, "{unrelated}");
+
+        assert_eq!(correlation_of((0..8).map(|_| (0.0, 0.0))), 0.0);
+    }
+
+    #[test]
+    fn correlation_needs_the_meter_and_two_channels() {
+        let ring = RingBuffer::new(4, 4, 1000).with_channels(2);
+        ring.write(vec![1.0, 1.0, -1.0, -1.0]).unwrap();
+        assert_eq!(ring.stereo_correlation(), 0.0);
+
+        let ring = RingBuffer::new(4, 4, 1000).with_correlation_meter(true);
+        ring.write(vec![1.0, 1.0, -1.0, -1.0]).unwrap();
+        assert_eq!(ring.stereo_correlation(), 0.0);
+    }
+
+    #[test]
+    fn windows_slice_a_ramp_by_hop() {
+        let ring = RingBuffer::new(4, 4, 1000);
+        for start in [0, 4, 8] {
+            ring.write((start..start + 4).map(|i| i as f32).collect())
+                .unwrap();
+        }
+        let ramp =
+            |start: usize, len: usize| (start..start + len).map(|i| i as f32).collect::<Vec<_>>();
+
+        // a trailing partial frame is dropped
+        assert_eq!(ring.windows(5, 3), [ramp(0, 5), ramp(3, 5), ramp(6, 5)]);
+        assert_eq!(ring.windows(4, 4), [ramp(0, 4), ramp(4, 4), ramp(8, 4)]);
+        assert_eq!(ring.windows(12, 1), [ramp(0, 12)]);
+        assert!(ring.windows(13, 1).is_empty());
+        assert!(ring.windows(0, 1).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "hop must be at least one sample")]
+    fn windows_reject_a_zero_hop() {
+        RingBuffer::new(4, 4, 1000).windows(4, 0);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{Clock, PacingMode, RingBufferError, SystemClock};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering};
+
+/// Single-producer, single-consumer ring of individual samples, for
+/// producers and consumers that don't work in whole blocks. Storage is one
+/// flat run of `ring_buffer_size * buffer_size` samples with a write and a
+/// read cursor counting samples since construction, so pushes and pops of
+/// any length can straddle the wrap.
+///
+/// Like [`RingBuffer`](crate::RingBuffer), a push never waits: once the
+/// ring is full the oldest unread samples are overwritten and the reader
+/// skips past them. Pops are paced by a [`PacingMode`] against a
+/// [`Clock`].
+pub struct SampleRing {
+    // f32 bits, so a writer lapping a pop mid-copy only hands the reader
+    // newer samples instead of racing on plain memory
+    samples: Vec<AtomicU32>,
+    clock: Box<dyn Clock>,
+    total_pushed: AtomicU64,
+    cursor: AtomicU64,
+    paced_since: AtomicU64,
+    paced_from: AtomicU64,
+    underruns: AtomicU64,
+    pacing_mode: AtomicU8,
+    buffer_size: usize,
+    sample_rate: f64,
+    channels: usize,
+}
+
+impl SampleRing {
+    pub fn new(buffer_size: usize, ring_buffer_size: usize, sample_rate: usize) -> Self {
+        Self::new_with_clock(
+            buffer_size,
+            ring_buffer_size,
+            sample_rate,
+            SystemClock::new(),
+        )
+    }
+
+    /// Like [`new`](Self::new), pacing pops against `clock` instead of the
+    /// system clock.
+    pub fn new_with_clock(
+        buffer_size: usize,
+        ring_buffer_size: usize,
+        sample_rate: usize,
+        clock: impl Clock + 'static,
+    ) -> Self {
+        let samples = (0..buffer_size * ring_buffer_size)
+            .map(|_| AtomicU32::new(0.0f32.to_bits()))
+            .collect();
+
+        Self {
+            samples,
+            paced_since: AtomicU64::new(clock.now().as_nanos() as u64),
+            clock: Box::new(clock),
+            total_pushed: AtomicU64::new(0),
+            cursor: AtomicU64::new(0),
+            paced_from: AtomicU64::new(0),
+            underruns: AtomicU64::new(0),
+            pacing_mode: AtomicU8::new(PacingMode::WallClock as u8),
+            buffer_size,
+            sample_rate: sample_rate as f64,
+            channels: 1,
+        }
+    }
+
+    /// Sets the number of interleaved channels, which scales how many
+    /// samples a second of pacing releases. Panics if `buffer_size` is not
+    /// a whole number of frames.
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        assert!(
+            channels > 0 && self.buffer_size.is_multiple_of(channels),
+            "buffer_size must be a multiple of channels"
+        );
+        self.channels = channels;
+        self
+    }
+
+    /// Total samples the ring holds.
+    pub fn capacity(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Appends `data`, overwriting the oldest samples once the ring is
+    /// full. Only the last [`capacity`](Self::capacity) samples of an
+    /// oversized push are kept.
+    pub fn push(&self, data: &[f32]) {
+        let head = self.total_pushed.load(Ordering::Relaxed);
+        let skip = data.len().saturating_sub(self.capacity());
+        for (i, sample) in data.iter().enumerate().skip(skip) {
+            self.slot(head + i as u64)
+                .store(sample.to_bits(), Ordering::Relaxed);
+        }
+
+        self.total_pushed
+            .fetch_add(data.len() as u64, Ordering::Release);
+    }
+
+    /// Pops up to `out.len()` samples and zero-fills the rest, returning
+    /// how many were real. With [`PacingMode::WallClock`] a pop only
+    /// reaches one block past what the clock says has played, so a reader
+    /// polling too eagerly gets fewer samples rather than racing ahead.
+    /// Running out of pushed samples counts as an underrun and restarts
+    /// pacing from the new position, as does skipping samples the writer
+    /// overwrote.
+    pub fn pop_into(&self, out: &mut [f32]) -> usize {
+        let head = self.total_pushed.load(Ordering::Acquire);
+        let cursor = self.read_position(head);
+        if cursor > self.cursor.load(Ordering::Relaxed) {
+            // overrun: pacing counted from before the skip would hold the
+            // reader back until the clock caught up with the lost samples
+            self.restart_pacing(cursor);
+        }
+        let due = match self.pacing_mode() {
+            PacingMode::WallClock => self.due(),
+            PacingMode::Unpaced => u64::MAX,
+        };
+
+        let count = head.min(due).saturating_sub(cursor).min(out.len() as u64) as usize;
+        for (i, sample) in out[..count].iter_mut().enumerate() {
+            *sample = f32::from_bits(self.slot(cursor + i as u64).load(Ordering::Relaxed));
+        }
+        out[count..].fill(0.0);
+
+        let cursor = cursor + count as u64;
+        self.cursor.store(cursor, Ordering::Release);
+        if count < out.len() && head < due {
+            self.underruns.fetch_add(1, Ordering::Relaxed);
+            self.restart_pacing(cursor);
+        }
+
+        count
+    }
+
+    /// Samples pushed but not yet popped.
+    pub fn available(&self) -> u64 {
+        let head = self.total_pushed.load(Ordering::Acquire);
+        head - self.read_position(head)
+    }
+
+    /// Samples pushed since construction.
+    pub fn total_pushed(&self) -> u64 {
+        self.total_pushed.load(Ordering::Acquire)
+    }
+
+    /// Absolute index of the sample the next pop will return first,
+    /// counting from the first sample ever pushed.
+    pub fn read_cursor(&self) -> u64 {
+        self.read_position(self.total_pushed.load(Ordering::Acquire))
+    }
+
+    /// Seeks the next pop to sample `pos`, which must still be resident:
+    /// no older than [`capacity`](Self::capacity) samples behind the write
+    /// cursor and not past it. Pacing restarts from `pos`. Errors with
+    /// `SequenceNotResident` otherwise and leaves the cursor alone.
+    pub fn set_read_cursor(&self, pos: u64) -> Result<(), RingBufferError> {
+        let head = self.total_pushed.load(Ordering::Acquire);
+        let oldest = head.saturating_sub(self.capacity() as u64);
+        if !(oldest..=head).contains(&pos) {
+            return Err(RingBufferError::SequenceNotResident);
+        }
+
+        self.cursor.store(pos, Ordering::Release);
+        self.restart_pacing(pos);
+
+        Ok(())
+    }
+
+    /// Number of pops that ran out of pushed samples.
+    pub fn underruns(&self) -> u64 {
+        self.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Switches how pops are paced. Pacing restarts from the current
+    /// position, so no backlog of clock time carries over.
+    pub fn set_pacing_mode(&self, mode: PacingMode) {
+        if self.pacing_mode.swap(mode as u8, Ordering::AcqRel) != mode as u8 {
+            self.restart_pacing(self.cursor.load(Ordering::Relaxed));
+        }
+    }
+
+    pub fn pacing_mode(&self) -> PacingMode {
+        if self.pacing_mode.load(Ordering::Acquire) == PacingMode::Unpaced as u8 {
+            PacingMode::Unpaced
+        } else {
+            PacingMode::WallClock
+        }
+    }
+
+    // The read cursor, moved past anything the writer has overwritten.
+    fn read_position(&self, head: u64) -> u64 {
+        let oldest = head.saturating_sub(self.capacity() as u64);
+        self.cursor.load(Ordering::Relaxed).max(oldest)
+    }
+
+    // One past the last sample a paced pop may return: what has played
+    // since pacing started, plus a block of lookahead.
+    fn due(&self) -> u64 {
+        let elapsed = self
+            .now_nanos()
+            .saturating_sub(self.paced_since.load(Ordering::Relaxed));
+        let played = elapsed as f64 / 1e9 * self.sample_rate * self.channels as f64;
+        self.paced_from.load(Ordering::Relaxed) + played as u64 + self.buffer_size as u64
+    }
+
+    fn restart_pacing(&self, from: u64) {
+        self.paced_since.store(self.now_nanos(), Ordering::Relaxed);
+        self.paced_from.store(from, Ordering::Relaxed);
+    }
+
+    fn now_nanos(&self) -> u64 {
+        self.clock.now().as_nanos() as u64
+    }
+
+    fn slot(&self, index: u64) -> &AtomicU32 {
+        &self.samples[(index % self.samples.len() as u64) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManualClock;
+    use std::time::Duration;
+
+    fn ramp(from: u64, len: usize) -> Vec<f32> {
+        (0..len as u64).map(|i| (from + i) as f32).collect()
+    }
+
+    #[test]
+    fn mismatched_chunks_straddle_the_wrap() {
+        let ring = SampleRing::new(4, 4, 1000);
+        ring.set_pacing_mode(PacingMode::Unpaced);
+
+        let mut pushed = 0;
+        let mut popped = Vec::new();
+        for round in 0..20 {
+            let len = 3 + round % 5;
+            ring.push(&ramp(pushed, len));
+            pushed += len as u64;
+
+            let mut out = vec![f32::NAN; 2 + round % 7];
+            let count = ring.pop_into(&mut out);
+            assert!(out[count..].iter().all(|&s| s == 0.0));
+            popped.extend_from_slice(&out[..count]);
+        }
+        let mut rest = vec![0.0; 16];
+        let count = ring.pop_into(&mut rest);
+        popped.extend_from_slice(&rest[..count]);
+
+        assert_eq!(popped, ramp(0, pushed as usize));
+        assert_eq!(ring.available(), 0);
+    }
+
+    #[test]
+    fn pops_resume_after_an_overrun() {
+        let clock = ManualClock::new();
+        let ring = SampleRing::new_with_clock(64, 4, 1000, clock.clone());
+
+        let mut pushed = 0;
+        let mut popped = 0;
+        for _ in 0..100 {
+            ring.push(&ramp(pushed, 70));
+            pushed += 70;
+            clock.advance(Duration::from_millis(64));
+
+            let mut out = vec![0.0; 64];
+            let count = ring.pop_into(&mut out);
+            assert!(count > 0, "silent after {popped} samples");
+            assert_eq!(out[0], ring.read_cursor() as f32 - count as f32);
+            popped += count;
+        }
+        assert!(ring.read_cursor() > pushed - ring.capacity() as u64);
+    }
+
+    #[test]
+    fn read_cursor_advances_by_the_samples_popped() {
+        let ring = SampleRing::new(4, 4, 1000);
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        ring.push(&ramp(0, 10));
+
+        let mut position = 0;
+        for len in [3, 1, 5] {
+            let mut out = vec![0.0; len];
+            assert_eq!(ring.pop_into(&mut out), len);
+            position += len as u64;
+            assert_eq!(ring.read_cursor(), position);
+        }
+
+        // a short pop only moves it by what was real
+        let mut out = vec![0.0; 4];
+        assert_eq!(ring.pop_into(&mut out), 1);
+        assert_eq!(ring.read_cursor(), 10);
+    }
+
+    #[test]
+    fn set_read_cursor_seeks_within_the_window_only() {
+        let ring = SampleRing::new(4, 4, 1000);
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        ring.push(&ramp(0, 20));
+
+        // the last 16 samples are resident, and seeking to the head is fine
+        for pos in [3, 21] {
+            assert!(matches!(
+                ring.set_read_cursor(pos),
+                Err(RingBufferError::SequenceNotResident)
+            ));
+            assert_eq!(ring.read_cursor(), 4);
+        }
+        ring.set_read_cursor(20).unwrap();
+        assert_eq!(ring.available(), 0);
+
+        ring.set_read_cursor(6).unwrap();
+        let mut out = vec![0.0; 3];
+        ring.pop_into(&mut out);
+        assert_eq!(out, ramp(6, 3));
+        assert_eq!(ring.read_cursor(), 9);
+    }
+}
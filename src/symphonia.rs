@@ -0,0 +1,65 @@
+use crate::{RingBuffer, RingBufferError};
+use symphonia_core::audio::{AudioBuffer, Channels, Signal, SignalSpec};
+
+impl RingBuffer {
+    /// Deinterleaves all unread audio, without consuming it, into a planar
+    /// symphonia `AudioBuffer` at the ring's sample rate. Interleaved channel
+    /// `n` becomes the `n`th channel in symphonia's order (front left, front
+    /// right, front centre, LFE, ...). Fails with `ChannelMismatch` when
+    /// there are more channels than symphonia can describe.
+    pub fn to_audio_buffer(&self) -> Result<AudioBuffer<f32>, RingBufferError> {
+        let (samples, sample_rate, channels) = self.to_interleaved();
+        let channels = channels as usize;
+        if channels > 26 {
+            return Err(RingBufferError::ChannelMismatch);
+        }
+
+        let spec = SignalSpec::new(
+            sample_rate,
+            Channels::from_bits_truncate(((1u64 << channels) - 1) as u32),
+        );
+        let frames = samples.len() / channels;
+        let mut buffer = AudioBuffer::new(frames as u64, spec);
+        buffer.render_reserved(Some(frames));
+
+        for channel in 0..channels {
+            let plane = buffer.chan_mut(channel);
+            for (out, frame) in plane.iter_mut().zip(samples.chunks_exact(channels)) {
+                *out = frame[channel];
+            }
+        }
+
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PacingMode, RingBuffer};
+    use symphonia_core::audio::{Channels, Signal};
+
+    #[test]
+    fn stereo_deinterleaves_into_planes() {
+        let ring = RingBuffer::new(4, 4, 44_100).with_channels(2);
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        ring.write(vec![9.0; 4]).unwrap();
+        ring.write(vec![0.1, -0.1, 0.2, -0.2]).unwrap();
+        ring.write(vec![0.3, -0.3, 0.4, -0.4]).unwrap();
+        ring.read();
+
+        let buffer = ring.to_audio_buffer().unwrap();
+        assert_eq!(buffer.spec().rate, 44_100);
+        assert_eq!(
+            buffer.spec().channels,
+            Channels::FRONT_LEFT | Channels::FRONT_RIGHT
+        );
+        assert_eq!(buffer.frames(), 4);
+        assert_eq!(buffer.chan(0), [0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(buffer.chan(1), [-0.1, -0.2, -0.3, -0.4]);
+
+        let interleaved: Vec<f32> = (0..buffer.frames())
+            .flat_map(|frame| [buffer.chan(0)[frame], buffer.chan(1)[frame]])
+            .collect();
+        assert_eq!(interleaved, ring.to_interleaved().0);
+    }
+}
@@ -0,0 +1,1482 @@
+use crate::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// 4 samples at 1 kHz: a 4 ms block period, so paced reads advance once
+// 3 ms have passed since the last advance.
+const BUFFER_SIZE: usize = 4;
+const SAMPLE_RATE: usize = 1000;
+const PERIOD: Duration = Duration::from_millis(4);
+
+fn manual_ring(ring_buffer_size: usize) -> (RingBuffer, ManualClock) {
+    let clock = ManualClock::new();
+    let ring =
+        RingBuffer::new_with_clock(BUFFER_SIZE, ring_buffer_size, SAMPLE_RATE, clock.clone());
+    (ring, clock)
+}
+
+// Block number `n`, counting from 1 so it can't be mistaken for silence.
+fn block(n: u64) -> Vec<f32> {
+    vec![n as f32; BUFFER_SIZE]
+}
+
+fn read(ring: &RingBuffer) -> (Vec<f32>, ReadOutcome) {
+    let mut out = vec![f32::NAN; ring.buffer_size()];
+    let outcome = ring.read_into(&mut out).unwrap();
+    (out, outcome)
+}
+
+#[test]
+fn max_age_drops_exactly_the_stale_blocks() {
+    let (ring, clock) = manual_ring(8);
+    let ring = ring.with_max_age(Duration::from_millis(80));
+
+    ring.write(block(1)).unwrap();
+    ring.write(block(2)).unwrap();
+    clock.advance(Duration::from_millis(50));
+    ring.write(block(3)).unwrap();
+    ring.write(block(4)).unwrap();
+    clock.advance(Duration::from_millis(40));
+
+    // seqs 0 and 1 are 90 ms old, 2 and 3 only 40 ms
+    let (out, outcome) = read(&ring);
+    assert_eq!(ring.aged_out(), 2);
+    assert_eq!(outcome.seq, Some(2));
+    assert_eq!(out, block(3));
+
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).1.seq, Some(3));
+    assert_eq!(ring.aged_out(), 2);
+}
+
+#[test]
+fn evicted_blocks_are_not_replayed() {
+    let (ring, clock) = manual_ring(8);
+    let ring = ring.with_max_age(Duration::from_millis(80));
+
+    for n in 1..=5 {
+        ring.write(block(n)).unwrap();
+    }
+    clock.advance(Duration::from_millis(200));
+
+    let (out, outcome) = read(&ring);
+    assert_eq!(ring.aged_out(), 5);
+    assert_eq!(outcome.seq, None);
+    assert!(outcome.underrun);
+    assert_eq!(out, vec![0.0; BUFFER_SIZE]);
+
+    // holding doesn't bring the last evicted block back either
+    let (out, outcome) = read(&ring);
+    assert_eq!(outcome.seq, None);
+    assert_eq!(out, vec![0.0; BUFFER_SIZE]);
+
+    ring.write(block(6)).unwrap();
+    clock.advance(PERIOD);
+    let (out, outcome) = read(&ring);
+    assert_eq!(outcome.seq, Some(5));
+    assert_eq!(out, block(6));
+}
+
+#[test]
+fn paused_time_does_not_age_blocks() {
+    let (ring, clock) = manual_ring(8);
+    let ring = ring.with_max_age(Duration::from_millis(80));
+
+    ring.write(block(1)).unwrap();
+    ring.write(block(2)).unwrap();
+    ring.pause();
+    clock.advance(Duration::from_millis(200));
+    ring.resume();
+    clock.advance(PERIOD);
+
+    assert_eq!(read(&ring).1.seq, Some(0));
+    assert_eq!(ring.aged_out(), 0);
+
+    // unpaused time does count: seq 1 is now 104 ms old
+    clock.advance(Duration::from_millis(100));
+    let (out, outcome) = read(&ring);
+    assert_eq!(ring.aged_out(), 1);
+    assert_eq!(outcome.seq, None);
+    assert_eq!(out, vec![0.0; BUFFER_SIZE]);
+}
+
+#[test]
+fn resuming_does_not_trigger_a_catch_up_storm() {
+    let (ring, clock) = manual_ring(32);
+    for n in 1..=14 {
+        ring.write(block(n)).unwrap();
+    }
+    for seq in 0..12 {
+        clock.advance(PERIOD);
+        assert_eq!(read(&ring).1.seq, Some(seq));
+    }
+
+    ring.pause();
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(read(&ring).1.seq, Some(11));
+    ring.resume();
+
+    // the ten paused seconds aren't owed: reads carry on one block a period
+    assert_eq!(read(&ring).1.seq, Some(11));
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).1.seq, Some(12));
+    assert_eq!(ring.catch_ups(), 0);
+}
+
+#[test]
+fn pacing_time_never_runs_backwards_across_pause_and_resume() {
+    let ring = Arc::new(RingBuffer::new(BUFFER_SIZE, 8, SAMPLE_RATE));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let toggler = {
+        let ring = ring.clone();
+        let done = done.clone();
+        std::thread::spawn(move || {
+            while !done.load(Ordering::Relaxed) {
+                ring.pause();
+                ring.resume();
+            }
+        })
+    };
+
+    let mut last = 0;
+    for _ in 0..200_000 {
+        let now = ring.now_nanos();
+        assert!(now >= last, "pacing time went from {last} to {now}");
+        last = now;
+    }
+    done.store(true, Ordering::Relaxed);
+    toggler.join().unwrap();
+}
+
+#[test]
+fn holding_a_lapped_block_serves_silence() {
+    let (ring, clock) = manual_ring(4);
+    ring.write(block(1)).unwrap();
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).1.seq, Some(0));
+
+    for n in 2..=5 {
+        ring.write(block(n)).unwrap();
+    }
+    // not due yet, so the read holds on seq 0, whose slot now has seq 4
+    let (out, outcome) = read(&ring);
+    assert_eq!(outcome.seq, None);
+    assert!(outcome.overwritten);
+    assert!(!outcome.fresh);
+    assert_eq!(out, vec![0.0; BUFFER_SIZE]);
+
+    clock.advance(PERIOD);
+    let (out, outcome) = read(&ring);
+    assert_eq!(outcome.seq, Some(1));
+    assert!(!outcome.overwritten);
+    assert_eq!(out, block(2));
+}
+
+// read_into rebuilt from the public cursor primitives, treating a block
+// that's no longer resident as silence.
+fn read_by_parts(ring: &RingBuffer) -> (Vec<f32>, Option<u64>) {
+    let mut out = vec![0.0; ring.buffer_size()];
+    let seq = ring.advance().or_else(|| ring.current_seq());
+    match seq.map(|seq| ring.copy_seq_into(seq, &mut out)) {
+        Some(Ok(())) => (out, seq),
+        Some(Err(RingBufferError::SequenceNotResident)) | None => (vec![0.0; out.len()], None),
+        Some(Err(err)) => panic!("{err:?}"),
+    }
+}
+
+#[test]
+fn read_into_matches_the_cursor_primitives() {
+    let (ring, clock) = manual_ring(4);
+    let (parts, parts_clock) = manual_ring(4);
+
+    let mut n = 0;
+    for step in 0..40 {
+        // bursts of writes that sometimes lap the held block
+        let writes = [0, 1, 5, 0, 2][step % 5];
+        for _ in 0..writes {
+            n += 1;
+            ring.write(block(n)).unwrap();
+            parts.write(block(n)).unwrap();
+        }
+        let (out, outcome) = read(&ring);
+        assert_eq!((out, outcome.seq), read_by_parts(&parts), "step {step}");
+
+        let elapsed = Duration::from_millis([1, 4, 2][step % 3]);
+        clock.advance(elapsed);
+        parts_clock.advance(elapsed);
+    }
+}
+
+#[test]
+fn discarded_blocks_are_not_replayed() {
+    let (ring, clock) = manual_ring(8);
+    for n in 1..=6 {
+        ring.write(block(n)).unwrap();
+    }
+    assert_eq!(ring.discard_blocks(2), 2);
+
+    // a read that isn't due must not serve the last discarded block
+    let (out, outcome) = read(&ring);
+    assert_eq!(outcome.seq, None);
+    assert_eq!(out, vec![0.0; BUFFER_SIZE]);
+
+    clock.advance(PERIOD);
+    let (out, outcome) = read(&ring);
+    assert_eq!(outcome.seq, Some(2));
+    assert_eq!(out, block(3));
+
+    // discarding nothing leaves the played block current
+    assert_eq!(ring.discard_blocks(0), 0);
+    assert_eq!(read(&ring).1.seq, Some(2));
+
+    assert_eq!(ring.discard_blocks(2), 2);
+    assert_eq!(read(&ring).1.seq, None);
+    clock.advance(PERIOD);
+    assert_eq!(
+        read(&ring),
+        (
+            block(6),
+            ReadOutcome {
+                seq: Some(5),
+                fresh: true,
+                ..Default::default()
+            }
+        )
+    );
+}
+
+#[test]
+fn render_all_renders_every_block_in_order() {
+    let (ring, _clock) = manual_ring(2);
+    let mut n = 0;
+    let rendered = ring.render_all(|| {
+        n += 1;
+        (n <= 5).then(|| block(n))
+    });
+
+    let expected: Vec<f32> = (1..=5).flat_map(block).collect();
+    assert_eq!(rendered, expected);
+    assert!(!ring.single_thread_misuse_detected());
+}
+
+#[test]
+fn snapshot_drains_to_the_unread_blocks() {
+    let (ring, clock) = manual_ring(4);
+    for n in 1..=3 {
+        ring.write(block(n)).unwrap();
+    }
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).1.seq, Some(0));
+    ring.write_gap(1).unwrap();
+    ring.write(block(5)).unwrap();
+
+    let snapshot = ring.snapshot_buffer();
+    assert_eq!(ring.available(), 4);
+
+    // paced by the live ring's clock, starting over from the snapshot
+    assert_eq!(read(&snapshot).1.seq, None);
+    let mut drained = Vec::new();
+    for _ in 0..4 {
+        clock.advance(PERIOD);
+        let (out, outcome) = read(&snapshot);
+        assert!(outcome.fresh);
+        drained.push(out);
+    }
+    assert_eq!(
+        drained,
+        [block(2), block(3), vec![0.0; BUFFER_SIZE], block(5)]
+    );
+}
+
+#[test]
+fn read_strategies_deliver_identical_data() {
+    let strategies = [
+        ReadStrategy::Copy,
+        ReadStrategy::Borrow,
+        ReadStrategy::Auto { threshold: 1 },
+        ReadStrategy::Auto { threshold: 1 << 20 },
+    ];
+    let rings: Vec<_> = strategies
+        .iter()
+        .map(|&strategy| {
+            let (ring, clock) = manual_ring(4);
+            (ring.with_read_strategy(strategy), clock)
+        })
+        .collect();
+
+    let mut n = 0;
+    for step in 0..60 {
+        // bursts that underrun, lap the held block and leave gaps
+        let writes = [1, 0, 6, 1, 0, 2][step % 6];
+        for _ in 0..writes {
+            n += 1;
+            for (ring, _) in &rings {
+                if n % 7 == 0 {
+                    ring.write_gap(1).unwrap();
+                } else {
+                    ring.write(block(n)).unwrap();
+                }
+            }
+        }
+
+        let reads: Vec<_> = rings
+            .iter()
+            .map(|(ring, clock)| {
+                clock.advance(Duration::from_millis([4, 1, 3][step % 3]));
+                ring.read_with(|data, outcome| (data.to_vec(), outcome))
+                    .unwrap()
+            })
+            .collect();
+        assert!(
+            reads.iter().all(|read| *read == reads[0]),
+            "step {step}: {reads:?}"
+        );
+    }
+}
+
+#[test]
+fn borrowed_blocks_are_not_recycled_while_lent() {
+    let ring =
+        Arc::new(RingBuffer::new(256, 2, SAMPLE_RATE).with_read_strategy(ReadStrategy::Borrow));
+    ring.set_pacing_mode(PacingMode::Unpaced);
+    let done = Arc::new(AtomicBool::new(false));
+
+    let writer = {
+        let ring = Arc::clone(&ring);
+        let done = Arc::clone(&done);
+        std::thread::spawn(move || {
+            let mut n = 0u32;
+            while !done.load(Ordering::Relaxed) {
+                n += 1;
+                ring.write_slice(&[n as f32; 256]).unwrap();
+            }
+        })
+    };
+
+    let mut lent = 0;
+    while lent < 20_000 {
+        ring.read_with(|data, outcome| {
+            if outcome.seq.is_some() {
+                // the writer recycling this block would tear it
+                assert!(data.iter().all(|&s| s == data[0]), "{data:?}");
+                lent += 1;
+            }
+        })
+        .unwrap();
+    }
+    done.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+}
+
+#[test]
+fn copied_blocks_are_not_recycled_mid_copy() {
+    let ring = Arc::new(RingBuffer::new(256, 4, SAMPLE_RATE));
+    ring.set_pacing_mode(PacingMode::Unpaced);
+    let done = Arc::new(AtomicBool::new(false));
+
+    let writer = {
+        let ring = Arc::clone(&ring);
+        let done = Arc::clone(&done);
+        std::thread::spawn(move || {
+            let mut n = 0u32;
+            while !done.load(Ordering::Relaxed) {
+                n += 1;
+                ring.write_slice(&[n as f32; 256]).unwrap();
+            }
+        })
+    };
+    // a second reader peeking at the same time needs a hazard of its own
+    let peeker = {
+        let ring = Arc::clone(&ring);
+        let done = Arc::clone(&done);
+        std::thread::spawn(move || {
+            let mut out = [0.0; 256];
+            while !done.load(Ordering::Relaxed) {
+                ring.read_latest(&mut out).unwrap();
+                assert!(out.iter().all(|&s| s == out[0]), "{out:?}");
+            }
+        })
+    };
+
+    let mut out = [0.0; 256];
+    let mut copied = 0;
+    while copied < 1_000 {
+        if ring.read_next_into(&mut out).unwrap() {
+            assert!(out.iter().all(|&s| s == out[0]), "{out:?}");
+            copied += 1;
+        }
+    }
+    done.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+    peeker.join().unwrap();
+}
+
+#[test]
+fn speed_up_drains_a_backlog_at_max_rate_without_skipping() {
+    let (ring, clock) = manual_ring(64);
+    let ring = ring.with_catch_up(CatchUp::SpeedUp {
+        max_rate: 2.0,
+        target: 2,
+    });
+    for n in 1..=60 {
+        ring.write(block(n)).unwrap();
+    }
+
+    // poll much faster than the block period, noting when each block starts
+    let step = Duration::from_micros(250);
+    let mut now = Duration::ZERO;
+    let mut advances = Vec::new();
+    while advances.len() < 60 {
+        clock.advance(step);
+        now += step;
+        let outcome = read(&ring).1;
+        if outcome.fresh {
+            advances.push((now, outcome.seq.unwrap()));
+        }
+    }
+
+    let seqs: Vec<_> = advances.iter().map(|&(_, seq)| seq).collect();
+    assert_eq!(seqs, (0..60).collect::<Vec<_>>());
+    assert_eq!(ring.catch_ups(), 0);
+    assert_eq!(ring.speed_ups(), 1);
+
+    // once ramped up, blocks last 0.75 of a period at twice the speed
+    for pair in advances[30..50].windows(2) {
+        let interval = pair[1].0 - pair[0].0;
+        assert!(
+            (Duration::from_micros(1500)..=Duration::from_micros(1750)).contains(&interval),
+            "{interval:?}"
+        );
+    }
+
+    // with the backlog gone it settles back to real time
+    for n in 61..=90 {
+        ring.write(block(n)).unwrap();
+        clock.advance(PERIOD);
+        read(&ring);
+    }
+    assert!(!ring.is_speeding_up());
+    assert_eq!(ring.speed_ups(), 1);
+}
+
+#[test]
+fn no_read_path_moves_the_head_past_the_writes() {
+    let (ring, clock) = manual_ring(8);
+    let ring = ring.with_max_age(Duration::from_millis(20));
+    let check = |ring: &RingBuffer, op: &str| {
+        assert!(
+            ring.total_reads() <= ring.total_writes(),
+            "{op}: read {} of {} writes",
+            ring.total_reads(),
+            ring.total_writes()
+        );
+    };
+
+    let mut n = 0;
+    let mut out = vec![0.0; BUFFER_SIZE];
+    let mut padded = vec![0.0; BUFFER_SIZE * 3];
+    for round in 0..30 {
+        for _ in 0..round % 14 {
+            n += 1;
+            ring.write(block(n)).unwrap();
+        }
+
+        clock.advance(Duration::from_millis(round % 5 * 8));
+        read(&ring);
+        check(&ring, "paced read, maybe catching up or aging out");
+        ring.discard_blocks(round as usize);
+        check(&ring, "discard_blocks");
+        ring.claim_next();
+        check(&ring, "claim_next");
+        ring.read_next_into(&mut out).unwrap();
+        check(&ring, "read_next_into");
+        ring.read_coalesced_padded(&mut padded).unwrap();
+        check(&ring, "read_coalesced_padded");
+
+        ring.set_pacing_mode(PacingMode::Unpaced);
+        for _ in 0..3 {
+            read(&ring);
+            ring.advance();
+        }
+        check(&ring, "unpaced reads");
+        ring.set_pacing_mode(PacingMode::WallClock);
+    }
+    assert!(ring.catch_ups() > 0, "{}", ring.catch_ups());
+    assert!(ring.aged_out() > 0, "{}", ring.aged_out());
+}
+
+#[test]
+fn write_rate_is_measured_on_tiny_rings() {
+    for ring_buffer_size in [1, 2, 8] {
+        let (ring, clock) = manual_ring(ring_buffer_size);
+        assert_eq!(ring.measured_write_rate(), 0.0);
+
+        // a block of 4 samples every 2 ms
+        for n in 1..=10 {
+            ring.write(block(n)).unwrap();
+            clock.advance(Duration::from_millis(2));
+        }
+        assert_eq!(
+            ring.measured_write_rate(),
+            2000.0,
+            "ring of {ring_buffer_size}"
+        );
+
+        // the window is short, so a new cadence shows up within a few writes
+        for n in 11..=20 {
+            ring.write(block(n)).unwrap();
+            clock.advance(Duration::from_millis(4));
+        }
+        assert_eq!(
+            ring.measured_write_rate(),
+            1000.0,
+            "ring of {ring_buffer_size}"
+        );
+    }
+}
+
+#[test]
+fn coalesced_reads_zero_pad_the_final_chunk() {
+    let (ring, _clock) = manual_ring(8);
+    for n in 1..=5 {
+        ring.write(block(n)).unwrap();
+    }
+
+    let mut out = vec![f32::NAN; BUFFER_SIZE * 3];
+    assert_eq!(ring.read_coalesced_padded(&mut out).unwrap(), 12);
+    assert_eq!(out, [block(1), block(2), block(3)].concat());
+
+    out.fill(f32::NAN);
+    assert_eq!(ring.read_coalesced_padded(&mut out).unwrap(), 8);
+    assert_eq!(out, [block(4), block(5), vec![0.0; BUFFER_SIZE]].concat());
+
+    assert_eq!(ring.read_coalesced_padded(&mut out).unwrap(), 0);
+    assert_eq!(out, vec![0.0; BUFFER_SIZE * 3]);
+    assert!(matches!(
+        ring.read_coalesced_padded(&mut out[..5]),
+        Err(RingBufferError::DataSizeMismatch)
+    ));
+}
+
+#[test]
+fn aggregates_ignore_never_written_slots() {
+    // a loud fill would dominate every aggregate if it leaked in
+    let ring = RingBuffer::new_with_fill(BUFFER_SIZE, 8, SAMPLE_RATE, 9.0);
+    ring.write(vec![0.5, -0.5, 0.5, -0.5]).unwrap();
+    ring.write(vec![-0.25; BUFFER_SIZE]).unwrap();
+
+    let (samples, _, _) = ring.to_interleaved();
+    assert_eq!(samples, [0.5, -0.5, 0.5, -0.5, -0.25, -0.25, -0.25, -0.25]);
+    assert_eq!(ring.ring_peak(), 0.5);
+    assert_eq!(ring.ring_rms(), (0.625f32 / 4.0).sqrt());
+
+    let mut window = vec![f32::NAN; BUFFER_SIZE * 4];
+    ring.analysis_window(&mut window);
+    assert!(window[..BUFFER_SIZE * 2].iter().all(|&s| s == 0.0));
+    assert_eq!(window[BUFFER_SIZE * 2..], samples[..]);
+}
+
+#[test]
+fn disabled_auto_catchup_advances_one_block_per_read() {
+    let (ring, clock) = manual_ring(32);
+    let ring = ring.with_auto_catchup(false);
+    for n in 1..=30 {
+        ring.write(block(n)).unwrap();
+    }
+
+    for seq in 0..20 {
+        clock.advance(PERIOD);
+        // alternate between the two paced read paths
+        let (out, got) = if seq % 2 == 0 {
+            let (out, outcome) = read(&ring);
+            (out, outcome.seq)
+        } else {
+            ring.read_seq()
+        };
+        assert_eq!(got, Some(seq));
+        assert_eq!(out, block(seq + 1));
+    }
+    assert_eq!(ring.catch_ups(), 0);
+    assert_eq!(ring.available(), 10);
+
+    // the same lag with it enabled snaps straight to the writer
+    let (ring, clock) = manual_ring(32);
+    for n in 1..=30 {
+        ring.write(block(n)).unwrap();
+    }
+    for _ in 0..13 {
+        clock.advance(PERIOD);
+        read(&ring);
+    }
+    assert_eq!(ring.catch_ups(), 1);
+    assert_eq!(ring.available(), 0);
+}
+
+#[test]
+fn write_chunked_accepts_whole_blocks_only() {
+    let (ring, _clock) = manual_ring(4);
+    ring.set_pacing_mode(PacingMode::Unpaced);
+
+    assert_eq!(
+        ring.write_chunked(&[block(1), block(2)].concat()).unwrap(),
+        8
+    );
+    assert_eq!(ring.total_writes(), 2);
+
+    // the trailing partial block is left for the caller
+    assert_eq!(
+        ring.write_chunked(&[block(3), vec![4.0; 3]].concat())
+            .unwrap(),
+        4
+    );
+    assert_eq!(ring.total_writes(), 3);
+
+    assert_eq!(ring.write_chunked(&[]).unwrap(), 0);
+    assert_eq!(ring.total_writes(), 3);
+
+    // more than the ring holds keeps the newest blocks
+    let many: Vec<f32> = (4..=9).flat_map(block).collect();
+    assert_eq!(ring.write_chunked(&many).unwrap(), 24);
+    assert_eq!(ring.total_writes(), 9);
+    for n in 6..=9 {
+        assert_eq!(read(&ring).0, block(n));
+    }
+    assert_eq!(ring.available(), 0);
+}
+
+#[test]
+fn cleared_blocks_are_silent_on_every_read_path() {
+    let (ring, clock) = manual_ring(8);
+    let ring = ring.with_clear_on_read(true);
+    ring.write(block(1)).unwrap();
+    ring.write(block(2)).unwrap();
+
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).0, block(1));
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).0, block(2));
+
+    let silence = vec![0.0; BUFFER_SIZE];
+    let mut out = vec![f32::NAN; BUFFER_SIZE];
+
+    // holding the current block
+    let (held, outcome) = read(&ring);
+    assert!(!outcome.fresh);
+    assert_eq!(held, silence);
+
+    for seq in 0..2 {
+        ring.copy_seq_into(seq, &mut out).unwrap();
+        assert_eq!(out, silence, "seq {seq}");
+    }
+
+    ring.read_latest(&mut out).unwrap();
+    assert_eq!(out, silence);
+
+    clock.advance(PERIOD / 2);
+    ring.peek_interpolated(&mut out).unwrap();
+    assert_eq!(out, silence);
+
+    let mut window = vec![f32::NAN; BUFFER_SIZE * 2];
+    ring.analysis_window(&mut window);
+    assert_eq!(window, vec![0.0; BUFFER_SIZE * 2]);
+
+    // running dry falls back on the delivered block
+    clock.advance(PERIOD);
+    let (starved, outcome) = read(&ring);
+    assert!(outcome.underrun);
+    assert_eq!(starved, silence);
+}
+
+#[test]
+fn last_read_was_fresh_tracks_each_read() {
+    let (ring, clock) = manual_ring(8);
+    assert!(!ring.last_read_was_fresh());
+    for n in 1..=4 {
+        ring.write(block(n)).unwrap();
+    }
+
+    for n in 1..=4 {
+        clock.advance(PERIOD);
+        assert_eq!(ring.read(), block(n));
+        assert!(ring.last_read_was_fresh(), "block {n}");
+
+        // not due again yet, so the same block repeats
+        ring.read();
+        assert!(!ring.last_read_was_fresh(), "repeat of block {n}");
+    }
+}
+
+#[test]
+fn to_interleaved_exports_unread_stereo_audio() {
+    let ring = RingBuffer::new(4, 4, 44_100).with_channels(2);
+    ring.write(vec![0.1, -0.1, 0.2, -0.2]).unwrap();
+    ring.write(vec![0.3, -0.3, 0.4, -0.4]).unwrap();
+    ring.write(vec![0.5, -0.5, 0.6, -0.6]).unwrap();
+    ring.set_pacing_mode(PacingMode::Unpaced);
+    ring.read();
+
+    let (samples, sample_rate, channels) = ring.to_interleaved();
+    assert_eq!(samples, [0.3, -0.3, 0.4, -0.4, 0.5, -0.5, 0.6, -0.6]);
+    assert_eq!(sample_rate, 44_100);
+    assert_eq!(channels, 2);
+    // exporting doesn't consume anything
+    assert_eq!(ring.available(), 2);
+}
+
+#[test]
+fn try_new_with_budget_rejects_only_over_budget_storage() {
+    // 256 samples of 4 bytes
+    let ring = RingBuffer::try_new_with_budget(64, 4, SAMPLE_RATE, 1024).unwrap();
+    assert_eq!(ring.buffer_size(), 64);
+
+    assert!(matches!(
+        RingBuffer::try_new_with_budget(64, 4, SAMPLE_RATE, 1023),
+        Err(RingBufferError::MemoryBudgetExceeded {
+            requested: 1024,
+            budget: 1023
+        })
+    ));
+    // a product that overflows usize counts as over any budget
+    assert!(matches!(
+        RingBuffer::try_new_with_budget(usize::MAX / 2, 4, SAMPLE_RATE, usize::MAX - 1),
+        Err(RingBufferError::MemoryBudgetExceeded { .. })
+    ));
+}
+
+#[test]
+fn gaps_are_paced_like_blocks_and_are_not_underruns() {
+    let (ring, clock) = manual_ring(8);
+    ring.write(block(1)).unwrap();
+    ring.write_gap(2).unwrap();
+    ring.write(block(2)).unwrap();
+    ring.write_gap(1).unwrap();
+    ring.write(block(3)).unwrap();
+
+    let expected = [
+        (block(1), false),
+        (vec![0.0; BUFFER_SIZE], true),
+        (vec![0.0; BUFFER_SIZE], true),
+        (block(2), false),
+        (vec![0.0; BUFFER_SIZE], true),
+        (block(3), false),
+    ];
+    for (seq, (data, intentional)) in expected.into_iter().enumerate() {
+        // a gap isn't due any sooner than a block
+        clock.advance(PERIOD / 2);
+        assert!(!read(&ring).1.fresh, "seq {seq} came early");
+
+        clock.advance(PERIOD / 2);
+        let (out, outcome) = read(&ring);
+        assert_eq!(outcome.seq, Some(seq as u64));
+        assert!(outcome.fresh);
+        assert_eq!(outcome.intentional, intentional, "seq {seq}");
+        assert!(!outcome.underrun, "seq {seq}");
+        assert_eq!(out, data, "seq {seq}");
+    }
+    assert_eq!(ring.underruns(), 0);
+
+    clock.advance(PERIOD);
+    assert!(read(&ring).1.underrun);
+    assert_eq!(ring.underruns(), 1);
+}
+
+#[test]
+fn read_planar_splits_stereo_and_rejects_other_layouts() {
+    let clock = ManualClock::new();
+    let ring = RingBuffer::new_with_clock(6, 4, SAMPLE_RATE, clock.clone()).with_channels(2);
+    ring.write(vec![1.0, -1.0, 2.0, -2.0, 3.0, -3.0]).unwrap();
+    clock.advance(Duration::from_millis(3));
+
+    let (left, right) = ring.read_planar().unwrap();
+    assert_eq!(left, [1.0, 2.0, 3.0]);
+    assert_eq!(right, [-1.0, -2.0, -3.0]);
+
+    for channels in [1, 3] {
+        let ring = RingBuffer::new(6, 4, SAMPLE_RATE).with_channels(channels);
+        assert!(matches!(
+            ring.read_planar(),
+            Err(RingBufferError::ChannelMismatch)
+        ));
+    }
+}
+
+#[test]
+fn underrun_fill_runs_once_per_underrun_only() {
+    use std::sync::atomic::AtomicUsize;
+
+    let (ring, clock) = manual_ring(8);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    ring.set_underrun_fill(move |out| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        out.fill(7.0);
+    });
+
+    ring.write(block(1)).unwrap();
+    ring.write(block(2)).unwrap();
+    for n in 1..=2 {
+        clock.advance(PERIOD);
+        let (out, outcome) = read(&ring);
+        assert!(outcome.fresh && !outcome.synthetic);
+        assert_eq!(out, block(n));
+
+        // holding a block between advances isn't an underrun
+        let (out, outcome) = read(&ring);
+        assert!(!outcome.fresh && !outcome.synthetic);
+        assert_eq!(out, block(n));
+    }
+    assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+    for underruns in 1..=3 {
+        clock.advance(PERIOD);
+        let (out, outcome) = read(&ring);
+        assert!(outcome.underrun && outcome.synthetic);
+        assert_eq!(out, vec![7.0; BUFFER_SIZE]);
+        assert_eq!(calls.load(Ordering::Relaxed), underruns);
+    }
+    assert_eq!(ring.synthetic_fills(), 3);
+
+    ring.write(block(3)).unwrap();
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).0, block(3));
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn unchecked_paths_accept_block_sized_data() {
+    let (ring, clock) = manual_ring(4);
+    ring.write_unchecked(block(1)).unwrap();
+    clock.advance(PERIOD);
+
+    let mut out = vec![0.0; BUFFER_SIZE];
+    assert!(ring.read_into_unchecked(&mut out).unwrap().fresh);
+    assert_eq!(out, block(1));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "assertion `left == right` failed")]
+fn write_unchecked_asserts_the_length_in_debug() {
+    let (ring, _clock) = manual_ring(4);
+    let _ = ring.write_unchecked(vec![0.0; BUFFER_SIZE + 1]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "assertion `left == right` failed")]
+fn read_into_unchecked_asserts_the_length_in_debug() {
+    let (ring, _clock) = manual_ring(4);
+    let _ = ring.read_into_unchecked(&mut [0.0; BUFFER_SIZE - 1]);
+}
+
+#[test]
+fn new_with_fill_fills_every_fresh_slot() {
+    let ring = RingBuffer::new_with_fill(BUFFER_SIZE, 4, SAMPLE_RATE, -1.5);
+    let mut slot = vec![0.0; BUFFER_SIZE];
+    for index in 0..4 {
+        ring.copy_slot_into(index, &mut slot);
+        assert_eq!(slot, [-1.5; BUFFER_SIZE], "slot {index}");
+    }
+
+    ring.write(block(1)).unwrap();
+    ring.copy_slot_into(0, &mut slot);
+    assert_eq!(slot, block(1));
+    ring.copy_slot_into(1, &mut slot);
+    assert_eq!(slot, [-1.5; BUFFER_SIZE]);
+
+    // NaN survives too, which is the point of the sentinel
+    let ring = RingBuffer::new_with_fill(BUFFER_SIZE, 4, SAMPLE_RATE, f32::NAN);
+    ring.copy_slot_into(2, &mut slot);
+    assert!(slot.iter().all(|s| s.is_nan()));
+}
+
+#[test]
+fn read_seq_counts_blocks_and_shows_catch_up_gaps() {
+    let (ring, clock) = manual_ring(32);
+    for n in 1..=30 {
+        ring.write(block(n)).unwrap();
+    }
+
+    let mut seqs = Vec::new();
+    while ring.available() > 0 {
+        clock.advance(PERIOD);
+        let (data, seq) = ring.read_seq();
+        let seq = seq.unwrap();
+        assert_eq!(data, block(seq + 1));
+        seqs.push(seq);
+    }
+
+    // one block per read until the lag forces a snap to the newest block
+    let steps: Vec<u64> = seqs.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    let (jump, steady) = steps.split_last().unwrap();
+    assert!(steady.iter().all(|&step| step == 1), "{seqs:?}");
+    assert!(*jump > 1, "{seqs:?}");
+    assert_eq!(seqs.last(), Some(&29));
+    assert_eq!(ring.catch_ups(), 1);
+}
+
+#[test]
+fn run_consumer_hands_over_each_block_in_order() {
+    let ring = RingBuffer::new(BUFFER_SIZE, 8, SAMPLE_RATE);
+    for n in 1..=5 {
+        ring.write(block(n)).unwrap();
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut seen = Vec::new();
+    ring.run_consumer(
+        |data| {
+            seen.push(data.to_vec());
+            if seen.len() == 5 {
+                stop.store(true, Ordering::Relaxed);
+            }
+        },
+        Arc::clone(&stop),
+    );
+
+    assert_eq!(seen, (1..=5).map(block).collect::<Vec<_>>());
+    assert_eq!(ring.available(), 0);
+}
+
+#[test]
+fn soft_clip_bounds_loud_samples_smoothly() {
+    let clock = ManualClock::new();
+    let ring = RingBuffer::new_with_clock(64, 4, 16_000, clock.clone()).with_soft_clip(0.5);
+    let ramp: Vec<f32> = (0..64).map(|i| i as f32 / 16.0).collect();
+    ring.write(ramp.clone()).unwrap();
+    ring.write(ramp.iter().map(|s| -s).collect()).unwrap();
+
+    for sign in [1.0, -1.0] {
+        clock.advance(Duration::from_millis(4));
+        let (out, _) = read(&ring);
+        let out: Vec<f32> = out.iter().map(|s| s * sign).collect();
+
+        for (&clipped, &input) in out.iter().zip(&ramp) {
+            if input <= 0.5 {
+                assert_eq!(clipped, input);
+            } else {
+                assert!(clipped < input.min(1.0), "{input} -> {clipped}");
+            }
+        }
+        // still rising all the way up, with no corner at the threshold
+        let slopes: Vec<f32> = out.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        assert!(slopes.iter().all(|&slope| slope > 0.0));
+        assert!(slopes.windows(2).all(|pair| pair[1] <= pair[0] + 1e-6));
+        assert!(slopes[8] > 0.9 / 16.0);
+    }
+}
+
+#[test]
+fn peek_interpolated_blends_by_elapsed_fraction() {
+    let (ring, clock) = manual_ring(8);
+    ring.write(block(1)).unwrap();
+    ring.write(block(3)).unwrap();
+    clock.advance(PERIOD);
+    read(&ring);
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).1.seq, Some(1));
+
+    // a quarter of the way from block 1 to block 3 per millisecond,
+    // holding at block 3 once the period is over
+    let advanced_at = clock.now();
+    let mut out = vec![f32::NAN; BUFFER_SIZE];
+    for (elapsed_ms, expected) in [(0, 1.0), (1, 1.5), (2, 2.0), (3, 2.5), (6, 3.0)] {
+        clock.set(advanced_at + Duration::from_millis(elapsed_ms));
+        ring.peek_interpolated(&mut out).unwrap();
+        assert_eq!(out, [expected; BUFFER_SIZE], "{elapsed_ms} ms in");
+    }
+}
+
+#[test]
+fn drain_trimmed_cuts_only_the_trailing_silence() {
+    let (ring, _clock) = manual_ring(8);
+    ring.write(vec![0.0, 0.5, 0.001, -0.3]).unwrap();
+    ring.write(vec![0.002, 0.0, -0.001, 0.0]).unwrap();
+    ring.write(vec![0.0; BUFFER_SIZE]).unwrap();
+
+    // leading and inner quiet samples stay
+    assert_eq!(ring.drain_trimmed(0.01), [0.0, 0.5, 0.001, -0.3]);
+    assert_eq!(ring.available(), 0);
+
+    ring.write(vec![0.001; BUFFER_SIZE]).unwrap();
+    assert!(ring.drain_trimmed(0.01).is_empty());
+    assert!(ring.drain_trimmed(0.01).is_empty());
+}
+
+#[test]
+fn overflow_handler_receives_each_overwritten_unread_block() {
+    let (ring, clock) = manual_ring(4);
+    let spilled = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = Arc::clone(&spilled);
+    ring.set_overflow_handler(Box::new(move |data| {
+        sink.lock().unwrap().push(data.to_vec())
+    }));
+
+    ring.write(block(1)).unwrap();
+    ring.write_gap(1).unwrap();
+    ring.write(block(3)).unwrap();
+    ring.write(block(4)).unwrap();
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).0, block(1));
+
+    // block 1 was read and the gap is silence, so neither is spilled
+    for n in 5..=8 {
+        ring.write(block(n)).unwrap();
+    }
+    assert_eq!(*spilled.lock().unwrap(), [block(3), block(4)]);
+}
+
+#[test]
+#[cfg(feature = "metrics")]
+fn steady_state_write_slice_does_not_allocate() {
+    let (ring, _clock) = manual_ring(4);
+    let mut out = vec![0.0; BUFFER_SIZE];
+    for n in 1..=8 {
+        ring.write_slice(&block(n)).unwrap();
+    }
+
+    let warmed_up = ring.allocation_count();
+    for n in 9..=100 {
+        ring.write_slice(&block(n)).unwrap();
+        if n % 3 == 0 {
+            ring.read_next_into(&mut out).unwrap();
+        }
+    }
+    assert_eq!(ring.allocation_count(), warmed_up);
+
+    // write boxes the caller's Vec every time
+    ring.write(block(101)).unwrap();
+    assert_eq!(ring.allocation_count(), warmed_up + 1);
+}
+
+#[test]
+fn smoothed_latency_follows_lag_changes_gradually() {
+    let (ring, _clock) = manual_ring(32);
+    let ring = ring.with_auto_catchup(false);
+    ring.set_pacing_mode(PacingMode::Unpaced);
+    assert_eq!(ring.smoothed_latency(), Duration::ZERO);
+
+    // one read that leaves `lag` blocks unread, then the estimate in blocks
+    let mut n = 0;
+    let mut step = |lag: u64| {
+        while ring.available() < lag + 1 {
+            n += 1;
+            ring.write(block(n)).unwrap();
+        }
+        read(&ring);
+        ring.smoothed_latency().as_secs_f64() / PERIOD.as_secs_f64()
+    };
+
+    for _ in 0..100 {
+        step(2);
+    }
+    assert!((step(2) - 2.0).abs() < 1e-6);
+    // polling doesn't consume anything, so the estimate only moves on reads
+    assert_eq!(ring.smoothed_latency(), ring.smoothed_latency());
+
+    // a jump in lag moves the estimate a tenth of the way per read
+    let estimates: Vec<f64> = (0..60).map(|_| step(10)).collect();
+    assert!((estimates[0] - 2.8).abs() < 1e-6, "{}", estimates[0]);
+    assert!((estimates[1] - 3.52).abs() < 1e-6, "{}", estimates[1]);
+    assert!(estimates.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!((10.0 - estimates[59]).abs() < 0.02, "{}", estimates[59]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn panic_on_overrun_fires_on_the_first_overrun() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let (ring, clock) = manual_ring(4);
+    let ring = ring.with_panic_on_overrun(true);
+    for n in 1..=4 {
+        ring.write(block(n)).unwrap();
+    }
+    clock.advance(PERIOD);
+    read(&ring);
+    // reuses the slot of the block just read
+    ring.write(block(5)).unwrap();
+
+    let overrun = catch_unwind(AssertUnwindSafe(|| ring.write(block(6))));
+    let message = overrun.unwrap_err();
+    assert_eq!(
+        message.downcast_ref::<String>().unwrap(),
+        "write 5 overwrites unread block 1: the reader has only claimed 1 of a 4-block ring"
+    );
+}
+
+#[test]
+fn latency_bounds_count_frames_not_samples() {
+    // 480 samples at 48 kHz: 10 ms of mono, 5 ms of stereo frames
+    let mono = RingBuffer::new(480, 8, 48_000);
+    assert_eq!(mono.min_latency(), Duration::from_millis(10));
+    assert_eq!(mono.max_latency(), Duration::from_millis(80));
+
+    let stereo = RingBuffer::new(480, 8, 48_000).with_channels(2);
+    assert_eq!(stereo.min_latency(), Duration::from_millis(5));
+    assert_eq!(stereo.max_latency(), Duration::from_millis(40));
+}
+
+#[test]
+fn self_test_passes_without_touching_live_state() {
+    for (buffer_size, ring_buffer_size, channels) in [(4, 4, 1), (6, 1, 2), (512, 16, 2)] {
+        let ring = RingBuffer::new(buffer_size, ring_buffer_size, 48_000).with_channels(channels);
+        ring.write(vec![0.25; buffer_size]).unwrap();
+
+        assert!(ring.self_test().is_ok());
+        assert_eq!(ring.total_writes(), 1);
+        assert_eq!(ring.available(), 1);
+        assert_eq!(ring.to_interleaved().0, vec![0.25; buffer_size]);
+    }
+}
+
+#[test]
+fn self_test_fails_on_live_corruption() {
+    let (ring, _clock) = manual_ring(4);
+    ring.write(block(1)).unwrap();
+    ring.replace_slot_for_test(2, vec![0.0; BUFFER_SIZE - 1]);
+    assert!(matches!(
+        ring.self_test(),
+        Err(RingBufferError::SelfTestFailed)
+    ));
+
+    let (ring, _clock) = manual_ring(4);
+    ring.write(block(1)).unwrap();
+    ring.set_counters_for_test(1, 3);
+    assert!(matches!(
+        ring.self_test(),
+        Err(RingBufferError::SelfTestFailed)
+    ));
+    // the live counters are left as they were
+    assert_eq!(ring.total_reads(), 3);
+}
+
+#[test]
+fn into_blocks_returns_slots_oldest_first() {
+    // the ring is still dropped afterwards, so freeing a taken slot again
+    // would show up here under a sanitizer or Miri
+    let ring = RingBuffer::new(BUFFER_SIZE, 4, SAMPLE_RATE);
+    ring.write(block(1)).unwrap();
+    ring.write(block(2)).unwrap();
+    assert_eq!(ring.into_blocks(), [block(0), block(0), block(1), block(2)]);
+
+    // after a wrap, starting from the oldest block still held
+    let (ring, _clock) = manual_ring(4);
+    for n in 1..=6 {
+        ring.write_slice(&block(n)).unwrap();
+    }
+    assert_eq!(ring.into_blocks(), [block(3), block(4), block(5), block(6)]);
+}
+
+#[test]
+fn into_blocks_silences_unwritten_and_gap_slots() {
+    // slots that were never written hold the fill, which isn't audio
+    let ring = RingBuffer::new_with_fill(BUFFER_SIZE, 4, SAMPLE_RATE, -1.0);
+    ring.write(block(1)).unwrap();
+    assert_eq!(ring.into_blocks(), [block(0), block(0), block(0), block(1)]);
+
+    // a gap written over an old block leaves that block in its slot
+    let (ring, _clock) = manual_ring(4);
+    for n in 1..=4 {
+        ring.write_slice(&block(n)).unwrap();
+    }
+    ring.write_gap(1).unwrap();
+    ring.write_slice(&block(6)).unwrap();
+    assert_eq!(ring.into_blocks(), [block(3), block(4), block(0), block(6)]);
+}
+
+#[test]
+fn e2e_latency_measures_write_to_claim_time() {
+    let (ring, clock) = manual_ring(8);
+    assert_eq!(ring.measured_e2e_latency(), Duration::ZERO);
+
+    clock.advance(Duration::from_millis(100));
+    ring.write(block(1)).unwrap();
+    clock.advance(Duration::from_millis(7));
+    assert!(read(&ring).1.fresh);
+    assert_eq!(ring.measured_e2e_latency(), Duration::from_millis(7));
+
+    // later claims move the average a tenth of the way
+    ring.write(block(2)).unwrap();
+    clock.advance(Duration::from_millis(17));
+    assert_eq!(ring.claim_next(), Some(1));
+    assert_eq!(ring.measured_e2e_latency(), Duration::from_millis(8));
+}
+
+#[test]
+fn read_prefix_into_copies_the_start_of_each_block() {
+    let (ring, clock) = manual_ring(8);
+    ring.write(vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+    ring.write(vec![5.0, 6.0, 7.0, 8.0]).unwrap();
+
+    clock.advance(PERIOD);
+    let mut short = [f32::NAN; 2];
+    assert_eq!(ring.read_prefix_into(&mut short).unwrap(), 2);
+    assert_eq!(short, [1.0, 2.0]);
+    assert_eq!(ring.total_reads(), 1);
+
+    // a longer `out` only gets one block, the rest is left alone
+    clock.advance(PERIOD);
+    let mut long = [f32::NAN; 6];
+    assert_eq!(ring.read_prefix_into(&mut long).unwrap(), 4);
+    assert_eq!(long[..4], [5.0, 6.0, 7.0, 8.0]);
+    assert!(long[4..].iter().all(|s| s.is_nan()));
+}
+
+#[test]
+fn default_matches_the_documented_geometry() {
+    let ring = RingBuffer::default();
+    assert_eq!(ring.buffer_size(), DEFAULT_BUFFER_SIZE);
+    assert_eq!(ring.ring_buffer_size(), DEFAULT_RING_BUFFER_SIZE);
+    assert_eq!(ring.sample_rate(), DEFAULT_SAMPLE_RATE);
+    assert_eq!(ring.channels(), 1);
+    assert_eq!(ring.pacing_mode(), PacingMode::WallClock);
+    assert_eq!(ring.total_writes(), 0);
+    // 8 blocks of 512 at 48 kHz
+    assert_eq!(ring.max_latency().as_micros(), 85_333);
+}
+
+#[test]
+fn stereo_counts_samples_and_frames_separately() {
+    let clock = ManualClock::new();
+    let ring = RingBuffer::new_with_clock(8, 8, SAMPLE_RATE, clock.clone()).with_channels(2);
+    for n in 1..=3 {
+        ring.write(vec![n as f32; 8]).unwrap();
+    }
+    clock.advance(PERIOD);
+    read(&ring);
+
+    assert_eq!((ring.samples_written(), ring.frames_written()), (24, 12));
+    assert_eq!((ring.samples_read(), ring.frames_read()), (8, 4));
+    assert_eq!((ring.samples_available(), ring.frames_available()), (16, 8));
+}
+
+#[test]
+fn stereo_blocks_are_paced_by_frames() {
+    // 8 samples are 4 stereo frames, 4 ms at 1 kHz, so due after 3 ms
+    let clock = ManualClock::new();
+    let stereo = RingBuffer::new_with_clock(8, 8, SAMPLE_RATE, clock.clone()).with_channels(2);
+    let mono = RingBuffer::new_with_clock(8, 8, SAMPLE_RATE, clock.clone());
+    for ring in [&stereo, &mono] {
+        ring.write(vec![1.0; 8]).unwrap();
+        ring.write(vec![2.0; 8]).unwrap();
+    }
+
+    clock.advance(PERIOD);
+    assert!(read(&stereo).1.fresh);
+    assert!(!read(&mono).1.fresh);
+
+    clock.advance(Duration::from_millis(2));
+    assert!(!read(&stereo).1.fresh);
+    clock.advance(Duration::from_millis(1));
+    assert!(read(&stereo).1.fresh);
+    // mono's 8 ms period puts it at 6 ms
+    assert!(read(&mono).1.fresh);
+}
+
+#[test]
+fn switching_pacing_mode_mid_stream_changes_how_reads_advance() {
+    let (ring, clock) = manual_ring(16);
+    for n in 1..=8 {
+        ring.write(block(n)).unwrap();
+    }
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).1.seq, Some(0));
+    assert!(!read(&ring).1.fresh);
+
+    // unpaced, every read advances without the clock moving
+    ring.set_pacing_mode(PacingMode::Unpaced);
+    for seq in 1..=3 {
+        assert_eq!(read(&ring).1.seq, Some(seq));
+    }
+
+    // back on the wall clock, the time spent unpaced isn't made up in a burst
+    clock.advance(Duration::from_millis(100));
+    ring.set_pacing_mode(PacingMode::WallClock);
+    assert!(!read(&ring).1.fresh);
+    clock.advance(PERIOD);
+    assert_eq!(read(&ring).1.seq, Some(4));
+    assert!(!read(&ring).1.fresh);
+    assert_eq!(ring.catch_ups(), 0);
+}
+
+#[test]
+fn recommended_ring_size_doubles_the_peak_lag() {
+    let (ring, clock) = manual_ring(8);
+    for n in 1..=3 {
+        ring.write(block(n)).unwrap();
+    }
+    assert_eq!(ring.peak_lag(), 3);
+    // never below the current size
+    assert_eq!(ring.recommended_ring_size(), 8);
+
+    for n in 4..=6 {
+        ring.write(block(n)).unwrap();
+    }
+    assert_eq!(ring.peak_lag(), 6);
+    assert_eq!(ring.recommended_ring_size(), 12);
+
+    // the peak holds once the reader catches up
+    for _ in 0..4 {
+        clock.advance(PERIOD);
+        read(&ring);
+    }
+    ring.write(block(7)).unwrap();
+    assert_eq!(ring.available(), 3);
+    assert_eq!(ring.recommended_ring_size(), 12);
+}
+
+#[test]
+fn compare_to_reports_the_first_divergence() {
+    let signal: Vec<f32> = (0..8).map(|i| i as f32 * 0.1).collect();
+    let filled = |data: &[f32]| {
+        let (ring, _clock) = manual_ring(4);
+        ring.write_chunked(data).unwrap();
+        ring
+    };
+
+    assert_eq!(filled(&signal).compare_to(&signal, 0.0), Ok(()));
+
+    // within epsilon passes, beyond it fails at the first bad index
+    let mut nudged = signal.clone();
+    nudged[3] += 0.001;
+    nudged[6] -= 0.5;
+    assert_eq!(
+        filled(&nudged).compare_to(&signal, 0.01),
+        Err((6, nudged[6], signal[6]))
+    );
+    assert!(filled(&nudged).compare_to(&signal, 1.0).is_ok());
+
+    // a side that runs out reports NaN
+    let (index, actual, expected) = filled(&signal[..4]).compare_to(&signal, 0.01).unwrap_err();
+    assert_eq!((index, expected), (4, signal[4]));
+    assert!(actual.is_nan());
+    let (index, actual, expected) = filled(&signal).compare_to(&signal[..6], 0.01).unwrap_err();
+    assert_eq!((index, actual), (6, signal[6]));
+    assert!(expected.is_nan());
+}
+
+#[test]
+fn agc_attacks_fast_and_releases_slowly_towards_the_target() {
+    // 10 ms blocks; a 20 ms attack and a 200 ms release
+    let ring = RingBuffer::new(100, 4, 10_000).with_agc(
+        0.1,
+        Duration::from_millis(20),
+        Duration::from_millis(200),
+    );
+    ring.set_pacing_mode(PacingMode::Unpaced);
+    let mut out = vec![0.0; 100];
+    let mut output_rms = |level: f32, blocks: usize| -> Vec<f32> {
+        (0..blocks)
+            .map(|_| {
+                ring.write(vec![level; 100]).unwrap();
+                ring.read_into(&mut out).unwrap();
+                (out.iter().map(|s| s * s).sum::<f32>() / 100.0).sqrt()
+            })
+            .collect()
+    };
+
+    // too quiet: each block closes 1 - e^(-10/200) of the gap to gain 10
+    let quiet = output_rms(0.01, 1);
+    let release = 1.0 - (-10.0f32 / 200.0).exp();
+    assert!((ring.agc_gain() - (1.0 + 9.0 * release)).abs() < 1e-4);
+    let quiet = [quiet, output_rms(0.01, 99)].concat();
+    assert!(quiet.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(quiet[20] < 0.08, "{}", quiet[20]);
+    assert!((quiet[99] - 0.1).abs() < 0.001, "{}", quiet[99]);
+
+    // too loud: each block closes 1 - e^(-10/20) of the gap to gain 0.2
+    let gain = ring.agc_gain();
+    let loud = output_rms(0.5, 1);
+    let attack = 1.0 - (-10.0f32 / 20.0).exp();
+    assert!((ring.agc_gain() - (gain + (0.2 - gain) * attack)).abs() < 1e-4);
+    let loud = [loud, output_rms(0.5, 19)].concat();
+    assert!(loud[0] > 1.0, "{}", loud[0]);
+    assert!(loud.windows(2).all(|pair| pair[0] > pair[1]));
+    assert!((loud[19] - 0.1).abs() < 0.001, "{}", loud[19]);
+}
+
+#[cfg(feature = "tracing")]
+mod tracing_events {
+    use super::*;
+    use std::fmt::Debug;
+    use std::sync::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::{span, Event, Level, Metadata, Subscriber};
+
+    // Level and fields of an event, the message included.
+    type Captured = (Level, Vec<(&'static str, String)>);
+
+    // Records every event; spans are ignored.
+    #[derive(Default)]
+    struct Capture(Arc<Mutex<Vec<Captured>>>);
+
+    struct Fields<'a>(&'a mut Vec<(&'static str, String)>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.push((field.name(), format!("{value:?}")));
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Vec::new();
+            event.record(&mut Fields(&mut fields));
+            self.0
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), fields));
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn a_forced_catch_up_emits_a_warning() {
+        let capture = Capture::default();
+        let events = Arc::clone(&capture.0);
+
+        tracing::subscriber::with_default(capture, || {
+            let (ring, clock) = manual_ring(32);
+            for n in 1..=30 {
+                ring.write(block(n)).unwrap();
+            }
+            while ring.catch_ups() == 0 {
+                clock.advance(PERIOD);
+                read(&ring);
+            }
+        });
+
+        let events = events.lock().unwrap();
+        let caught_up: Vec<_> = events
+            .iter()
+            .filter(|(_, fields)| {
+                fields.contains(&("message", "ring buffer reader caught up".to_string()))
+            })
+            .collect();
+        assert_eq!(caught_up.len(), 1, "{events:?}");
+
+        let (level, fields) = caught_up[0];
+        assert_eq!(*level, Level::WARN);
+        assert!(fields.contains(&("catch_ups", "1".to_string())));
+        assert!(fields.contains(&("seq", "29".to_string())));
+        assert!(fields.iter().any(|(name, _)| *name == "lag"));
+    }
+}
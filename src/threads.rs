@@ -0,0 +1,94 @@
+use crate::RingBuffer;
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(debug_assertions)]
+use std::sync::OnceLock;
+#[cfg(debug_assertions)]
+use std::thread::{self, ThreadId};
+
+/// Remembers which threads first wrote to and first read from the ring, so
+/// a producer and consumer interleaved on one thread can be flagged. Only
+/// active in debug builds.
+#[derive(Default)]
+pub(crate) struct ThreadCheck {
+    #[cfg(debug_assertions)]
+    writer: OnceLock<ThreadId>,
+    #[cfg(debug_assertions)]
+    reader: OnceLock<ThreadId>,
+    #[cfg(debug_assertions)]
+    same_thread: AtomicBool,
+    #[cfg(debug_assertions)]
+    allowed: AtomicBool,
+}
+
+impl ThreadCheck {
+    // For internal scratch rings that are meant to be used from one thread.
+    pub(crate) fn allow_same_thread(&self) {
+        #[cfg(debug_assertions)]
+        self.allowed.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn note_writer(&self) {
+        #[cfg(debug_assertions)]
+        self.note(&self.writer, &self.reader);
+    }
+
+    pub(crate) fn note_reader(&self) {
+        #[cfg(debug_assertions)]
+        self.note(&self.reader, &self.writer);
+    }
+
+    #[cfg(debug_assertions)]
+    fn note(&self, mine: &OnceLock<ThreadId>, other: &OnceLock<ThreadId>) {
+        if mine.get().is_some() || self.allowed.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let id = thread::current().id();
+        if mine.set(id).is_ok() && other.get() == Some(&id) {
+            self.same_thread.store(true, Ordering::Relaxed);
+            #[cfg(feature = "tracing")]
+            tracing::warn!("ring buffer written and read from the same thread");
+        }
+    }
+}
+
+impl RingBuffer {
+    /// True when the first write and the first consuming read came from the
+    /// same thread, which hides pacing bugs the ring exists to absorb.
+    /// Always false in release builds.
+    pub fn single_thread_misuse_detected(&self) -> bool {
+        #[cfg(debug_assertions)]
+        return self.thread_check.same_thread.load(Ordering::Relaxed);
+        #[cfg(not(debug_assertions))]
+        return false;
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use crate::RingBuffer;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn flags_a_writer_and_reader_on_one_thread() {
+        let ring = RingBuffer::new(4, 4, 1000);
+        ring.write(vec![1.0; 4]).unwrap();
+        ring.read();
+
+        assert!(ring.single_thread_misuse_detected());
+    }
+
+    #[test]
+    fn separate_threads_are_not_flagged() {
+        let ring = Arc::new(RingBuffer::new(4, 4, 1000));
+        let writer = Arc::clone(&ring);
+        thread::spawn(move || writer.write(vec![1.0; 4]).unwrap())
+            .join()
+            .unwrap();
+        ring.read();
+
+        assert!(!ring.single_thread_misuse_detected());
+    }
+}
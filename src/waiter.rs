@@ -0,0 +1,156 @@
+use std::sync::Mutex;
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+/// A slot for one thread parked until another thread makes some condition
+/// true. Notifying never blocks, so the reader and writer can wake each
+/// other from their hot paths.
+#[derive(Default)]
+pub(crate) struct Waiter {
+    thread: Mutex<Option<Thread>>,
+}
+
+impl Waiter {
+    /// Parks the calling thread until `ready` returns a value, which is
+    /// passed back, or `deadline` passes. `ready` is rechecked on every
+    /// wake-up, including unparks from outside the ring.
+    pub(crate) fn wait<R>(
+        &self,
+        deadline: Option<Instant>,
+        mut ready: impl FnMut() -> Option<R>,
+    ) -> Option<R> {
+        *self.thread.lock().unwrap() = Some(thread::current());
+
+        // registered before checking, so a notify in between still unparks us
+        let result = loop {
+            if let Some(result) = ready() {
+                break Some(result);
+            }
+            match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break None;
+                    }
+                    thread::park_timeout(remaining);
+                }
+                None => thread::park(),
+            }
+        };
+
+        *self.thread.lock().unwrap() = None;
+        result
+    }
+
+    /// Unparks the waiting thread, if any.
+    pub(crate) fn notify(&self) {
+        // try_lock: whoever notifies must never block on the waiter
+        if let Ok(waiter) = self.thread.try_lock() {
+            if let Some(thread) = waiter.as_ref() {
+                thread.unpark();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{RingBuffer, RingBufferError};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn wait_for_catchup_wakes_on_a_forced_catch_up() {
+        let ring = Arc::new(RingBuffer::new(4, 32, 1_000_000));
+        let done = Arc::new(AtomicBool::new(false));
+
+        // keeps the reader far enough behind that paced reads snap forward
+        let lagger = {
+            let ring = Arc::clone(&ring);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    for _ in 0..8 {
+                        ring.write(vec![0.0; 4]).unwrap();
+                    }
+                    ring.read();
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+
+        let caught_up = ring.wait_for_catchup(Some(Duration::from_secs(5)));
+        done.store(true, Ordering::Relaxed);
+        lagger.join().unwrap();
+
+        assert!(caught_up);
+        assert!(ring.catch_ups() > 0);
+    }
+
+    #[test]
+    fn waits_end_on_close_or_timeout() {
+        let ring = Arc::new(RingBuffer::new(4, 2, 1000));
+        assert!(!ring.wait_for_catchup(Some(Duration::from_millis(10))));
+
+        let closer = {
+            let ring = Arc::clone(&ring);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                ring.close();
+            })
+        };
+        assert!(!ring.wait_for_catchup(None));
+        closer.join().unwrap();
+    }
+
+    // Writes `blocks` blocks from another thread, a millisecond apart.
+    fn spawn_writer(ring: &Arc<RingBuffer>, blocks: usize) -> thread::JoinHandle<()> {
+        let ring = Arc::clone(ring);
+        thread::spawn(move || {
+            for _ in 0..blocks {
+                thread::sleep(Duration::from_millis(1));
+                ring.write(vec![0.0; 4]).unwrap();
+            }
+        })
+    }
+
+    #[test]
+    fn wait_for_writes_wakes_once_the_count_is_reached() {
+        let ring = Arc::new(RingBuffer::new(4, 8, 1000));
+        let writer = spawn_writer(&ring, 5);
+
+        ring.wait_for_writes(5, Some(Duration::from_secs(5)))
+            .unwrap();
+        assert!(ring.total_writes() >= 5);
+        writer.join().unwrap();
+
+        assert!(matches!(
+            ring.wait_for_writes(6, Some(Duration::from_millis(10))),
+            Err(RingBufferError::TimedOut)
+        ));
+        ring.close();
+        assert!(matches!(
+            ring.wait_for_writes(6, None),
+            Err(RingBufferError::Closed)
+        ));
+    }
+
+    #[test]
+    fn wait_until_full_wakes_once_every_slot_is_written() {
+        let ring = Arc::new(RingBuffer::new(4, 6, 1000));
+        let writer = spawn_writer(&ring, 6);
+
+        ring.wait_until_full(Some(Duration::from_secs(5))).unwrap();
+        assert!(ring.is_fully_populated());
+        assert_eq!(ring.total_writes(), 6);
+        writer.join().unwrap();
+
+        let ring = RingBuffer::new(4, 6, 1000);
+        assert!(matches!(
+            ring.wait_until_full(Some(Duration::from_millis(10))),
+            Err(RingBufferError::TimedOut)
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{PerformanceClock, RingBuffer, RingBufferError};
+use js_sys::{Float32Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+fn error_code(err: &RingBufferError) -> &'static str {
+    match err {
+        RingBufferError::DataSizeMismatch => "DataSizeMismatch",
+        RingBufferError::SequenceNotResident => "SequenceNotResident",
+        RingBufferError::Closed => "Closed",
+        RingBufferError::MemoryBudgetExceeded { .. } => "MemoryBudgetExceeded",
+        RingBufferError::ChannelMismatch => "ChannelMismatch",
+        RingBufferError::FrameMismatch => "FrameMismatch",
+        RingBufferError::TimedOut => "TimedOut",
+        RingBufferError::SelfTestFailed => "SelfTestFailed",
+    }
+}
+
+fn to_js_error(err: RingBufferError) -> JsValue {
+    let js_err = js_sys::Error::new(&err.to_string());
+    let _ = Reflect::set(&js_err, &"code".into(), &error_code(&err).into());
+    js_err.into()
+}
+
+/// JavaScript-facing ring buffer, exported as `RingBuffer`.
+#[wasm_bindgen(js_name = RingBuffer)]
+pub struct WasmRingBuffer {
+    inner: RingBuffer,
+    scratch: Vec<f32>,
+}
+
+#[wasm_bindgen(js_class = RingBuffer)]
+impl WasmRingBuffer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer_size: usize, ring_buffer_size: usize, sample_rate: usize) -> Self {
+        let clock = PerformanceClock::new();
+
+        Self {
+            inner: RingBuffer::new_with_clock(buffer_size, ring_buffer_size, sample_rate, clock),
+            scratch: vec![0.0; buffer_size],
+        }
+    }
+
+    pub fn write(&self, data: &Float32Array) -> Result<(), JsValue> {
+        self.inner.write(data.to_vec()).map_err(to_js_error)
+    }
+
+    /// Paced read into `out`; returns whether a new block was delivered.
+    #[wasm_bindgen(js_name = readInto)]
+    pub fn read_into(&mut self, out: &Float32Array) -> Result<bool, JsValue> {
+        if out.length() as usize != self.scratch.len() {
+            return Err(to_js_error(RingBufferError::DataSizeMismatch));
+        }
+
+        let outcome = self
+            .inner
+            .read_into(&mut self.scratch)
+            .map_err(to_js_error)?;
+        out.copy_from(&self.scratch);
+
+        Ok(outcome.fresh)
+    }
+
+    pub fn available(&self) -> usize {
+        self.inner.available() as usize
+    }
+
+    pub fn stats(&self) -> JsValue {
+        let stats = Object::new();
+        let _ = Reflect::set(
+            &stats,
+            &"totalWrites".into(),
+            &(self.inner.total_writes() as f64).into(),
+        );
+        let _ = Reflect::set(
+            &stats,
+            &"totalReads".into(),
+            &(self.inner.total_reads() as f64).into(),
+        );
+        let _ = Reflect::set(
+            &stats,
+            &"available".into(),
+            &(self.inner.available() as f64).into(),
+        );
+        stats.into()
+    }
+}
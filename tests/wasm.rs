@@ -0,0 +1,58 @@
+//! Browser tests, run with `wasm-pack test --headless --firefox --features wasm-bindgen`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use wasm_bindgen_test::*;
+use weresocool_ring_buffer::{Clock, PacingMode, PerformanceClock, RingBuffer};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn performance_clock_paces_a_round_trip() {
+    let clock = PerformanceClock::new();
+    let before = clock.now();
+    let ring = RingBuffer::new_with_clock(4, 4, 1000, PerformanceClock::new());
+    ring.set_pacing_mode(PacingMode::Unpaced);
+
+    ring.write(vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+    let mut out = [0.0; 4];
+    let outcome = ring.read_into(&mut out).unwrap();
+
+    assert!(outcome.fresh);
+    assert_eq!(out, [1.0, 2.0, 3.0, 4.0]);
+    assert!(clock.now() >= before);
+}
+
+#[cfg(feature = "wasm-bindgen")]
+mod bindings {
+    use js_sys::{Date, Float32Array, Reflect};
+    use wasm_bindgen_test::*;
+    use weresocool_ring_buffer::WasmRingBuffer;
+
+    fn stat(stats: &wasm_bindgen::JsValue, key: &str) -> f64 {
+        Reflect::get(stats, &key.into()).unwrap().as_f64().unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn write_read_into_and_stats_round_trip() {
+        let mut ring = WasmRingBuffer::new(4, 4, 1000);
+        ring.write(&Float32Array::from(&[1.0, 2.0, 3.0, 4.0][..]))
+            .unwrap();
+        assert_eq!(ring.available(), 1);
+
+        // the first paced read is due one block period after construction
+        let out = Float32Array::new_with_length(4);
+        let deadline = Date::now() + 1000.0;
+        while !ring.read_into(&out).unwrap() {
+            assert!(Date::now() < deadline, "block never became due");
+        }
+        assert_eq!(out.to_vec(), [1.0, 2.0, 3.0, 4.0]);
+
+        let stats = ring.stats();
+        assert_eq!(stat(&stats, "totalWrites"), 1.0);
+        assert_eq!(stat(&stats, "totalReads"), 1.0);
+        assert_eq!(stat(&stats, "available"), 0.0);
+
+        assert!(ring.write(&Float32Array::new_with_length(3)).is_err());
+        assert!(ring.read_into(&Float32Array::new_with_length(3)).is_err());
+    }
+}